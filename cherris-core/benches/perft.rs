@@ -1,7 +1,26 @@
-use cherris_core::{Position, generate_lookup_tables, perft};
+use cherris_core::{MoveList, Position, generate_lookup_tables, generate_moves, perft};
 use criterion::{Criterion, criterion_group, criterion_main};
 use std::time::Duration;
 
+/// Perft that copies the position for every child instead of taking moves back.
+fn perft_copy_make(depth: u64, position: &Position) -> usize {
+    let mut moves = MoveList::new();
+    generate_moves(position, &mut moves);
+
+    if depth == 1 {
+        return moves.len();
+    }
+
+    let mut nodes = 0;
+    for mv in moves {
+        let mut next_position = *position;
+        next_position.make_move(mv);
+        nodes += perft_copy_make(depth - 1, &next_position);
+    }
+
+    nodes
+}
+
 pub fn perft_benchmark(c: &mut Criterion) {
     generate_lookup_tables();
 
@@ -10,7 +29,10 @@ pub fn perft_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("perft5");
 
     group.measurement_time(Duration::from_secs(10));
-    group.bench_function("perft 5", |b| b.iter(|| perft(5, &position)));
+    group.bench_function("perft 5 make-unmake", |b| b.iter(|| perft(5, &position)));
+    group.bench_function("perft 5 copy-make", |b| {
+        b.iter(|| perft_copy_make(5, &position))
+    });
 }
 
 criterion_group!(benches, perft_benchmark);
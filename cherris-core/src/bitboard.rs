@@ -22,6 +22,9 @@ impl Bitboard {
     pub const SEVENTH_RANK: Bitboard = Bitboard(0x00FF000000000000);
     pub const EIGTH_RANK: Bitboard = Bitboard(0xFF00000000000000);
 
+    /// All light squares, starting with B1.
+    pub const LIGHT_SQUARES: Bitboard = Bitboard(0x55AA55AA55AA55AA);
+
     pub const PROMOTION_RANK: [Bitboard; Color::COUNT] =
         [Bitboard::EIGTH_RANK, Bitboard::FIRST_RANK];
 
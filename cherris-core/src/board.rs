@@ -162,6 +162,25 @@ impl Board {
         self.role[Role::Rook] ^= rook_bb;
     }
 
+    /// Returns a `Bitboard` of the pieces of both colors that attack `square` when only the pieces
+    /// in `occupied` block the sliders.
+    pub fn attackers_to(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let bishops_queens = self.role[Role::Bishop] | self.role[Role::Queen];
+        let rooks_queens = self.role[Role::Rook] | self.role[Role::Queen];
+
+        let attackers =
+            (pawn_attacks(square, Color::White) & self.role[Role::Pawn] & self.color[Color::Black])
+                | (pawn_attacks(square, Color::Black)
+                    & self.role[Role::Pawn]
+                    & self.color[Color::White])
+                | (knight_attacks(square) & self.role[Role::Knight])
+                | (king_attacks(square) & self.role[Role::King])
+                | (bishop_attacks(square, occupied) & bishops_queens)
+                | (rook_attacks(square, occupied) & rooks_queens);
+
+        attackers & occupied
+    }
+
     /// Returns a `Bitboard` that indicates which sqaures are currently attacked by the pieces of
     /// the given color.
     pub fn attacked_sqaures(&self, color: Color) -> Bitboard {
@@ -209,6 +228,7 @@ impl Board {
     ///    sqaures a piece could move to to prevent the ceck. This includes the checker itself.
     /// 3. The king is in check by two pieces. A empty `Bitboard` will be returned as only the king
     ///    can move.
+    ///
     /// Any move can now be & with the returned bitboard to prune all illegal moves in regard to
     /// checks.
     pub fn check_mask(&self, color: Color) -> Bitboard {
@@ -315,6 +335,24 @@ impl Display for Board {
 mod tests {
     use super::*;
 
+    #[test]
+    fn attackers_to() {
+        let mut board = Board::EMPTY;
+        board.put_piece_on(Piece::WHITE_PAWN, Square::C4);
+        board.put_piece_on(Piece::BLACK_PAWN, Square::E6);
+        board.put_piece_on(Piece::BLACK_PAWN, Square::D6);
+        board.put_piece_on(Piece::WHITE_ROOK, Square::D1);
+        board.put_piece_on(Piece::WHITE_ROOK, Square::D2);
+        board.put_piece_on(Piece::BLACK_KNIGHT, Square::F6);
+
+        let attackers = board.attackers_to(Square::D5, board.occupied);
+
+        assert_eq!(
+            attackers,
+            Bitboard::from([Square::C4, Square::E6, Square::D2, Square::F6].as_slice())
+        );
+    }
+
     #[test]
     fn piece_on() {
         let mut board = Board::EMPTY;
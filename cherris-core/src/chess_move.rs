@@ -3,7 +3,7 @@ use std::{
     str::FromStr,
 };
 
-use crate::{Color, Error, Position, Role, Square};
+use crate::{Color, Error, File, MoveList, Position, Rank, Role, Square};
 
 /// Represents a move in a chess game.
 #[derive(Clone, Copy, PartialEq)]
@@ -61,6 +61,203 @@ impl Move {
 
         Err(Error::InvalidMove)
     }
+
+    /// Converts a legal move to Standard Algebraic Notation, including the disambiguation of the
+    /// moving piece and the check and mate suffixes.
+    pub fn to_san(&self, position: &Position) -> String {
+        let legal_moves = position.legal_moves();
+        let mut san = self.san_without_suffix(&legal_moves);
+
+        let mut next_position = *position;
+        next_position.make_move(*self);
+        if next_position.is_checkmate() {
+            san.push('#');
+        } else if next_position.is_in_check() {
+            san.push('+');
+        }
+
+        san
+    }
+
+    /// Finds the legal move written in Standard Algebraic Notation. Common variants are accepted
+    /// as well: castling with zeros, promotions without `=`, missing or superfluous capture signs
+    /// and disambiguation, check suffixes and annotations like `!?`.
+    pub fn from_san(san: &str, position: &Position) -> Result<Move, Error> {
+        let san = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+
+        let castling = match san {
+            "O-O" | "0-0" | "o-o" => Some(Move::CastleShort),
+            "O-O-O" | "0-0-0" | "o-o-o" => Some(Move::CastleLong),
+            _ => None,
+        };
+        if let Some(castling) = castling {
+            return position
+                .legal_moves()
+                .iter()
+                .copied()
+                .find(|mv| *mv == castling)
+                .ok_or(Error::InvalidMove);
+        }
+
+        let mut chars: Vec<char> = san
+            .chars()
+            .filter(|char| !matches!(char, 'x' | ':' | '-' | '='))
+            .collect();
+
+        // A letter right after the rank of the destination is a promotion.
+        let mut promotion = None;
+        if chars.len() >= 3
+            && chars[chars.len() - 1].is_ascii_alphabetic()
+            && chars[chars.len() - 2].is_ascii_digit()
+        {
+            let role = parse_char::<Role>(chars.pop().unwrap())?;
+            if matches!(role, Role::Pawn | Role::King) {
+                return Err(Error::InvalidMove);
+            }
+            promotion = Some(role);
+        }
+
+        if chars.len() < 2 {
+            return Err(Error::InvalidMove);
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Square::from_str(&to).map_err(|_| Error::InvalidMove)?;
+
+        let mut role = Role::Pawn;
+        if let Some(first) = chars.first()
+            && first.is_ascii_uppercase()
+        {
+            role = parse_char::<Role>(*first)?;
+            chars.remove(0);
+        }
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for char in chars {
+            match char {
+                'a'..='h' if from_file.is_none() => from_file = Some(parse_char::<File>(char)?),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(parse_char::<Rank>(char)?),
+                _ => return Err(Error::InvalidMove),
+            }
+        }
+
+        let matches_from = |from: &Square| {
+            let (file, rank) = <(File, Rank)>::from(from);
+            from_file.is_none_or(|from_file| from_file == file)
+                && from_rank.is_none_or(|from_rank| from_rank == rank)
+        };
+
+        let mut candidates = position.legal_moves().into_iter().filter(|mv| match mv {
+            Move::Standard {
+                from,
+                to: move_to,
+                role: move_role,
+                promotion: move_promotion,
+                ..
+            } => {
+                *move_to == to
+                    && *move_role == role
+                    && *move_promotion == promotion
+                    && matches_from(from)
+            }
+            Move::EnPassant {
+                from, to: move_to, ..
+            } => *move_to == to && role == Role::Pawn && promotion.is_none() && matches_from(from),
+            Move::CastleShort | Move::CastleLong => false,
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(chess_move), None) => Ok(chess_move),
+            _ => Err(Error::InvalidMove),
+        }
+    }
+
+    fn san_without_suffix(&self, legal_moves: &MoveList) -> String {
+        match *self {
+            Move::CastleShort => "O-O".to_string(),
+            Move::CastleLong => "O-O-O".to_string(),
+            Move::EnPassant { from, to, .. } => format!("{}x{}", file_of(from), to),
+            Move::Standard {
+                from,
+                to,
+                role: Role::Pawn,
+                capture,
+                promotion,
+                ..
+            } => {
+                let mut san = match capture {
+                    Some(_) => format!("{}x{}", file_of(from), to),
+                    None => to.to_string(),
+                };
+                if let Some(promotion) = promotion {
+                    san.push_str(&format!("={}", promotion));
+                }
+
+                san
+            }
+            Move::Standard {
+                from,
+                to,
+                role,
+                capture,
+                ..
+            } => {
+                let capture = if capture.is_some() { "x" } else { "" };
+
+                format!(
+                    "{}{}{}{}",
+                    role,
+                    disambiguation(from, to, role, legal_moves),
+                    capture,
+                    to
+                )
+            }
+        }
+    }
+}
+
+/// Returns the part of the from square that is needed to tell a move apart from other moves of
+/// the same kind of piece to the same square.
+fn disambiguation(from: Square, to: Square, role: Role, legal_moves: &MoveList) -> String {
+    let others: Vec<Square> = legal_moves
+        .iter()
+        .filter_map(|mv| match *mv {
+            Move::Standard {
+                from: other_from,
+                to: other_to,
+                role: other_role,
+                ..
+            } if other_to == to && other_role == role && other_from != from => Some(other_from),
+            _ => None,
+        })
+        .collect();
+
+    if others.is_empty() {
+        return String::new();
+    }
+
+    let (file, rank) = <(File, Rank)>::from(&from);
+
+    if others.iter().all(|other| file_of(*other) != file) {
+        file.to_string()
+    } else if others
+        .iter()
+        .all(|other| <(File, Rank)>::from(other).1 != rank)
+    {
+        rank.to_string()
+    } else {
+        from.to_string()
+    }
+}
+
+/// Parses a single character of a SAN move.
+fn parse_char<T: FromStr>(char: char) -> Result<T, Error> {
+    T::from_str(&char.to_string()).map_err(|_| Error::InvalidMove)
+}
+
+fn file_of(square: Square) -> File {
+    <(File, Rank)>::from(&square).0
 }
 
 impl Display for Move {
@@ -152,8 +349,101 @@ impl Display for LAN {
 
 #[cfg(test)]
 mod tests {
+    use crate::generate_lookup_tables;
+
     use super::*;
 
+    fn assert_san(fen: &str, san: &str) {
+        generate_lookup_tables();
+
+        let position = Position::from_str(fen).unwrap();
+        let chess_move = Move::from_san(san, &position).unwrap();
+
+        assert_eq!(chess_move.to_san(&position), san);
+    }
+
+    fn assert_san_variant(fen: &str, variant: &str, san: &str) {
+        generate_lookup_tables();
+
+        let position = Position::from_str(fen).unwrap();
+        let chess_move = Move::from_san(variant, &position).unwrap();
+
+        assert_eq!(chess_move.to_san(&position), san);
+    }
+
+    #[test]
+    fn san_pawn_moves() {
+        assert_san(Position::STARTING_FEN, "e4");
+        assert_san(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            "exd5",
+        );
+        assert_san("8/1P4k1/8/8/8/8/8/4K3 w - - 0 1", "b8=Q");
+        assert_san("8/6k1/8/8/8/8/8/4K3 w - - 0 1", "Ke2");
+    }
+
+    #[test]
+    fn san_en_passant() {
+        assert_san(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "exf6",
+        );
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        assert_san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "Rab1");
+        assert_san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "Rhg1");
+        assert_san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "R1a3");
+        assert_san("k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1", "Qc3d2");
+    }
+
+    #[test]
+    fn san_check_and_mate() {
+        assert_san("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", "Ra8#");
+        assert_san("6k1/8/8/8/8/8/5PPP/R5K1 w - - 0 1", "Ra8+");
+    }
+
+    #[test]
+    fn san_castling() {
+        assert_san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "O-O");
+        assert_san("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "O-O-O");
+    }
+
+    #[test]
+    fn san_variants() {
+        let fen = "r3k2r/1P6/8/3p4/4P3/8/3N4/R3K2R w KQkq - 0 1";
+        assert_san_variant(fen, "0-0", "O-O");
+        assert_san_variant(fen, "0-0-0", "O-O-O");
+        assert_san_variant(fen, "b8Q", "b8=Q+");
+        assert_san_variant(fen, "bxa8=n", "bxa8=N");
+        assert_san_variant(fen, "ed5", "exd5");
+        assert_san_variant(fen, "e4xd5", "exd5");
+        assert_san_variant(fen, "Nd2f3", "Nf3");
+        assert_san_variant(fen, "Nd2-f3", "Nf3");
+        assert_san_variant(fen, "Nf3!?", "Nf3");
+        assert_san_variant(fen, "Rxa7", "Ra7");
+    }
+
+    #[test]
+    fn san_invalid() {
+        generate_lookup_tables();
+
+        let position = Position::default();
+        for san in ["e5", "Nf4", "Ke2", "", "e", "Zf3", "exd5", "e8=K", "O-O"] {
+            assert_eq!(
+                Move::from_san(san, &position),
+                Err(Error::InvalidMove),
+                "{}",
+                san
+            );
+        }
+
+        // Without disambiguation the move is ambiguous.
+        let position = Position::from_str("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(Move::from_san("Rb1", &position), Err(Error::InvalidMove));
+    }
+
     #[test]
     fn move_from_lan() {
         let lan = LAN::from_str("e2e4").unwrap();
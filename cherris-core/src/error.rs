@@ -1,3 +1,5 @@
+use crate::FenError;
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     ParseColor,
@@ -5,6 +7,11 @@ pub enum Error {
     ParseRank,
     ParseRole,
     ParsePiece,
-    InvalidFen,
+    InvalidFen(FenError),
     InvalidMove,
+    /// The game has already ended.
+    GameOver,
+    /// The action is not allowed in the current state of the game, like accepting a draw that
+    /// was never offered.
+    InvalidGameAction,
 }
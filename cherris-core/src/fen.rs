@@ -0,0 +1,547 @@
+use std::fmt::Display;
+
+use crate::{
+    Bitboard, Board, CastlingRights, Color, File, Piece, Position, Rank, Role, Square, Zobrist,
+};
+
+/// How strictly a FEN is parsed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FenMode {
+    /// All six fields are required and nothing may follow them.
+    Strict,
+    /// The halfmove clock and fullmove number may be missing, as in EPD test suites, and anything
+    /// after the fields of the position is ignored.
+    Lenient,
+}
+
+/// The fields of a FEN.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FenField {
+    Board,
+    ColorToMove,
+    CastlingRights,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+/// The reason a FEN is invalid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FenErrorReason {
+    /// The field is missing.
+    MissingField,
+    /// There is more text after the last field.
+    TooManyFields,
+    /// The field contains a character that is not allowed there.
+    InvalidCharacter(char),
+    /// The board has more or less than eight ranks.
+    WrongRankCount(usize),
+    /// A rank describes more than eight squares.
+    TooManySquares,
+    /// A rank describes less than eight squares.
+    TooFewSquares,
+    /// A color has no king.
+    MissingKing(Color),
+    /// A color has more than one king.
+    TooManyKings(Color),
+    /// A pawn stands on the first or eighth rank.
+    PawnOnBackRank,
+    /// The color that just moved is in check.
+    OpponentInCheck,
+    /// A castling right appears more than once.
+    DuplicateCastlingRight(char),
+    /// The king or rook of a castling right is not on its starting square.
+    CastlingPiecesMissing(char),
+    /// The en passant square can't be the result of a double pawn push.
+    InvalidEnPassantSquare,
+    /// The field is not a valid number.
+    InvalidNumber,
+}
+
+/// Describes which part of a FEN is invalid and why.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FenError {
+    pub field: FenField,
+    pub file: Option<File>,
+    pub rank: Option<Rank>,
+    pub reason: FenErrorReason,
+}
+
+impl FenError {
+    fn new(field: FenField, reason: FenErrorReason) -> FenError {
+        FenError {
+            field,
+            file: None,
+            rank: None,
+            reason,
+        }
+    }
+
+    fn at(self, file: Option<File>, rank: Option<Rank>) -> FenError {
+        FenError { file, rank, ..self }
+    }
+}
+
+impl Display for FenField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenField::Board => write!(f, "board"),
+            FenField::ColorToMove => write!(f, "color to move"),
+            FenField::CastlingRights => write!(f, "castling rights"),
+            FenField::EnPassant => write!(f, "en passant square"),
+            FenField::HalfmoveClock => write!(f, "halfmove clock"),
+            FenField::FullmoveNumber => write!(f, "fullmove number"),
+        }
+    }
+}
+
+impl Display for FenErrorReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenErrorReason::MissingField => write!(f, "missing field"),
+            FenErrorReason::TooManyFields => write!(f, "unexpected text after the last field"),
+            FenErrorReason::InvalidCharacter(char) => write!(f, "invalid character '{}'", char),
+            FenErrorReason::WrongRankCount(ranks) => write!(f, "{} ranks instead of 8", ranks),
+            FenErrorReason::TooManySquares => write!(f, "more than 8 squares in a rank"),
+            FenErrorReason::TooFewSquares => write!(f, "less than 8 squares in a rank"),
+            FenErrorReason::MissingKing(color) => write!(f, "{} has no king", color),
+            FenErrorReason::TooManyKings(color) => write!(f, "{} has more than one king", color),
+            FenErrorReason::PawnOnBackRank => write!(f, "pawn on the back rank"),
+            FenErrorReason::OpponentInCheck => write!(f, "the color that moved is in check"),
+            FenErrorReason::DuplicateCastlingRight(char) => {
+                write!(f, "castling right '{}' appears twice", char)
+            }
+            FenErrorReason::CastlingPiecesMissing(char) => write!(
+                f,
+                "king or rook for castling right '{}' not on its starting square",
+                char
+            ),
+            FenErrorReason::InvalidEnPassantSquare => write!(f, "impossible en passant square"),
+            FenErrorReason::InvalidNumber => write!(f, "invalid number"),
+        }
+    }
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid {}: {}", self.field, self.reason)?;
+
+        match (self.file, self.rank) {
+            (Some(file), Some(rank)) => write!(f, " on {}{}", file, rank),
+            (None, Some(rank)) => write!(f, " on rank {}", rank),
+            (Some(file), None) => write!(f, " on file {}", file),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl Position {
+    /// Parses and validates a position in Forsyth-Edwards Notation.
+    pub fn from_fen(fen: &str, mode: FenMode) -> Result<Position, FenError> {
+        let mut fields = fen.split_whitespace();
+        let mut next_field = |field: FenField| {
+            fields
+                .next()
+                .ok_or(FenError::new(field, FenErrorReason::MissingField))
+        };
+
+        let board = parse_board(next_field(FenField::Board)?)?;
+        let color_to_move = parse_color(next_field(FenField::ColorToMove)?)?;
+        let castling_rights = parse_castling_rights(next_field(FenField::CastlingRights)?, &board)?;
+        let en_passant_square =
+            parse_en_passant(next_field(FenField::EnPassant)?, &board, color_to_move)?;
+
+        let (halfmove_clock, fullmove_number) = match mode {
+            FenMode::Strict => {
+                let halfmove_clock = parse_number(
+                    next_field(FenField::HalfmoveClock)?,
+                    FenField::HalfmoveClock,
+                )?;
+                let fullmove_number = parse_number(
+                    next_field(FenField::FullmoveNumber)?,
+                    FenField::FullmoveNumber,
+                )?;
+                if fullmove_number == 0 {
+                    return Err(FenError::new(
+                        FenField::FullmoveNumber,
+                        FenErrorReason::InvalidNumber,
+                    ));
+                }
+                if fields.next().is_some() {
+                    return Err(FenError::new(
+                        FenField::FullmoveNumber,
+                        FenErrorReason::TooManyFields,
+                    ));
+                }
+
+                (halfmove_clock, fullmove_number)
+            }
+            FenMode::Lenient => {
+                // EPD operations like `bm e4;` take the place of the clocks.
+                let halfmove_clock = fields.next().and_then(|field| field.parse().ok());
+                let fullmove_number = halfmove_clock
+                    .and_then(|_| fields.next())
+                    .and_then(|field| field.parse().ok())
+                    .filter(|fullmove_number| *fullmove_number > 0);
+
+                (halfmove_clock.unwrap_or(0), fullmove_number.unwrap_or(1))
+            }
+        };
+
+        let king = board.role[Role::King] & board.color[!color_to_move];
+        if !(board.attacked_sqaures(color_to_move) & king).is_empty() {
+            let (file, rank) = <(File, Rank)>::from(&king.to_square());
+            return Err(
+                FenError::new(FenField::Board, FenErrorReason::OpponentInCheck)
+                    .at(Some(file), Some(rank)),
+            );
+        }
+
+        let mut position = Position {
+            board,
+            color_to_move,
+            en_passant_square,
+            halfmove_clock,
+            fullmove_number,
+            castling_rights,
+            zobrist: Zobrist::DEFAULT,
+            pawn_key: Zobrist::DEFAULT,
+            material_key: Zobrist::DEFAULT,
+        };
+        position.zobrist = Zobrist::from_position(&position);
+        position.pawn_key = Zobrist::pawns_from_position(&position);
+        position.material_key = Zobrist::material_from_position(&position);
+
+        Ok(position)
+    }
+}
+
+fn parse_board(field: &str) -> Result<Board, FenError> {
+    let error = |reason| FenError::new(FenField::Board, reason);
+
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(error(FenErrorReason::WrongRankCount(ranks.len())));
+    }
+
+    let mut role = [Bitboard::EMPTY; Role::COUNT];
+    let mut color = [Bitboard::EMPTY; Color::COUNT];
+
+    for (rank_index, rank_str) in ranks.iter().enumerate() {
+        let rank = Rank::from_index(7 - rank_index);
+        let mut file_index = 0;
+
+        for char in rank_str.chars() {
+            if file_index >= 8 {
+                return Err(error(FenErrorReason::TooManySquares).at(None, Some(rank)));
+            }
+            let file = File::from_index(file_index);
+
+            match char {
+                '1'..='8' => file_index += char.to_digit(10).unwrap() as usize,
+                char => {
+                    let piece = Piece::try_from(char).map_err(|_| {
+                        error(FenErrorReason::InvalidCharacter(char)).at(Some(file), Some(rank))
+                    })?;
+                    if piece.role == Role::Pawn && (rank == Rank::First || rank == Rank::Eigth) {
+                        return Err(
+                            error(FenErrorReason::PawnOnBackRank).at(Some(file), Some(rank))
+                        );
+                    }
+
+                    let square_bb = Bitboard::from(Square::from((file, rank)));
+                    role[piece.role] |= square_bb;
+                    color[piece.color] |= square_bb;
+                    file_index += 1;
+                }
+            }
+        }
+
+        match file_index {
+            8 => {}
+            0..8 => return Err(error(FenErrorReason::TooFewSquares).at(None, Some(rank))),
+            _ => return Err(error(FenErrorReason::TooManySquares).at(None, Some(rank))),
+        }
+    }
+
+    for side in [Color::White, Color::Black] {
+        match (role[Role::King] & color[side]).population_count() {
+            1 => {}
+            0 => return Err(error(FenErrorReason::MissingKing(side))),
+            _ => return Err(error(FenErrorReason::TooManyKings(side))),
+        }
+    }
+
+    Ok(Board {
+        role,
+        color,
+        occupied: color[Color::White] | color[Color::Black],
+    })
+}
+
+fn parse_color(field: &str) -> Result<Color, FenError> {
+    match field {
+        "w" => Ok(Color::White),
+        "b" => Ok(Color::Black),
+        _ => Err(FenError::new(
+            FenField::ColorToMove,
+            FenErrorReason::InvalidCharacter(field.chars().next().unwrap_or(' ')),
+        )),
+    }
+}
+
+fn parse_castling_rights(
+    field: &str,
+    board: &Board,
+) -> Result<[CastlingRights; Color::COUNT], FenError> {
+    let error = |reason| FenError::new(FenField::CastlingRights, reason);
+
+    let mut rights = [[false; 2]; Color::COUNT];
+    if field != "-" {
+        for char in field.chars() {
+            let (side, king_side, king, rook) = match char {
+                'K' => (Color::White, true, Square::E1, Square::H1),
+                'Q' => (Color::White, false, Square::E1, Square::A1),
+                'k' => (Color::Black, true, Square::E8, Square::H8),
+                'q' => (Color::Black, false, Square::E8, Square::A8),
+                char => return Err(error(FenErrorReason::InvalidCharacter(char))),
+            };
+
+            let right = &mut rights[side][usize::from(!king_side)];
+            if *right {
+                return Err(error(FenErrorReason::DuplicateCastlingRight(char)));
+            }
+            *right = true;
+
+            let has_piece = |square: Square, role: Role| {
+                board.piece_on(square) == Some(Piece { color: side, role })
+            };
+            if !has_piece(king, Role::King) || !has_piece(rook, Role::Rook) {
+                return Err(error(FenErrorReason::CastlingPiecesMissing(char)));
+            }
+        }
+    }
+
+    Ok(
+        rights.map(|[king_side, queen_side]| match (king_side, queen_side) {
+            (true, true) => CastlingRights::BothSides,
+            (true, false) => CastlingRights::KingSide,
+            (false, true) => CastlingRights::QueenSide,
+            (false, false) => CastlingRights::NoSide,
+        }),
+    )
+}
+
+fn parse_en_passant(
+    field: &str,
+    board: &Board,
+    color_to_move: Color,
+) -> Result<Option<Square>, FenError> {
+    if field == "-" {
+        return Ok(None);
+    }
+
+    let error = |reason| FenError::new(FenField::EnPassant, reason);
+
+    let square: Square = field.parse().map_err(|_| {
+        error(FenErrorReason::InvalidCharacter(
+            field.chars().next().unwrap(),
+        ))
+    })?;
+    let (file, rank) = <(File, Rank)>::from(&square);
+
+    // The pawn that just moved two squares stands in front of the en passant square, and both
+    // the en passant square and the square the pawn came from have to be empty.
+    let (expected_rank, pawn_rank, from_rank) = match color_to_move {
+        Color::White => (Rank::Sixth, Rank::Fifth, Rank::Seventh),
+        Color::Black => (Rank::Third, Rank::Fourth, Rank::Second),
+    };
+    let pawn = Piece {
+        color: !color_to_move,
+        role: Role::Pawn,
+    };
+
+    if rank != expected_rank
+        || board.piece_on(Square::from((file, pawn_rank))) != Some(pawn)
+        || board.piece_on(square).is_some()
+        || board.piece_on(Square::from((file, from_rank))).is_some()
+    {
+        return Err(error(FenErrorReason::InvalidEnPassantSquare).at(Some(file), Some(rank)));
+    }
+
+    Ok(Some(square))
+}
+
+fn parse_number<T: std::str::FromStr>(field: &str, fen_field: FenField) -> Result<T, FenError> {
+    field
+        .parse()
+        .map_err(|_| FenError::new(fen_field, FenErrorReason::InvalidNumber))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Error, generate_lookup_tables};
+
+    use super::*;
+
+    fn strict_error(fen: &str) -> FenError {
+        generate_lookup_tables();
+
+        Position::from_fen(fen, FenMode::Strict).unwrap_err()
+    }
+
+    #[test]
+    fn valid_fens() {
+        generate_lookup_tables();
+
+        for fen in [
+            Position::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let position = Position::from_fen(fen, FenMode::Strict).unwrap();
+            assert_eq!(position.to_string(), fen);
+        }
+    }
+
+    #[test]
+    fn missing_field() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3"),
+            FenError::new(FenField::ColorToMove, FenErrorReason::MissingField)
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w - -"),
+            FenError::new(FenField::HalfmoveClock, FenErrorReason::MissingField)
+        );
+        assert_eq!(
+            Position::from_str(""),
+            Err(Error::InvalidFen(FenError::new(
+                FenField::Board,
+                FenErrorReason::MissingField
+            )))
+        );
+    }
+
+    #[test]
+    fn too_many_fields() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra").reason,
+            FenErrorReason::TooManyFields
+        );
+    }
+
+    #[test]
+    fn invalid_board() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3/8 w - - 0 1").reason,
+            FenErrorReason::WrongRankCount(9)
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K4 w - - 0 1"),
+            FenError::new(FenField::Board, FenErrorReason::TooManySquares)
+                .at(None, Some(Rank::First))
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/7/4K3 w - - 0 1"),
+            FenError::new(FenField::Board, FenErrorReason::TooFewSquares)
+                .at(None, Some(Rank::Second))
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/3X4/8/8/8/4K3 w - - 0 1"),
+            FenError::new(FenField::Board, FenErrorReason::InvalidCharacter('X'))
+                .at(Some(File::D), Some(Rank::Fifth))
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/8 w - - 0 1").reason,
+            FenErrorReason::MissingKing(Color::White)
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").reason,
+            FenErrorReason::TooManyKings(Color::White)
+        );
+        assert_eq!(
+            strict_error("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            FenError::new(FenField::Board, FenErrorReason::PawnOnBackRank)
+                .at(Some(File::A), Some(Rank::Eigth))
+        );
+        assert_eq!(
+            strict_error("4k2R/8/8/8/8/8/8/4K3 w - - 0 1"),
+            FenError::new(FenField::Board, FenErrorReason::OpponentInCheck)
+                .at(Some(File::E), Some(Rank::Eigth))
+        );
+    }
+
+    #[test]
+    fn invalid_castling_rights() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w K - 0 1").reason,
+            FenErrorReason::CastlingPiecesMissing('K')
+        );
+        assert_eq!(
+            strict_error("r3k2r/8/8/8/8/8/8/R3K2R w KQkqq - 0 1").reason,
+            FenErrorReason::DuplicateCastlingRight('q')
+        );
+        assert_eq!(
+            strict_error("r3k2r/8/8/8/8/8/8/R3K2R w KX - 0 1").reason,
+            FenErrorReason::InvalidCharacter('X')
+        );
+    }
+
+    #[test]
+    fn invalid_en_passant() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"),
+            FenError::new(FenField::EnPassant, FenErrorReason::InvalidEnPassantSquare)
+                .at(Some(File::E), Some(Rank::Third))
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w - e6 0 1").reason,
+            FenErrorReason::InvalidEnPassantSquare
+        );
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+            FenError::new(FenField::HalfmoveClock, FenErrorReason::InvalidNumber)
+        );
+        assert_eq!(
+            strict_error("4k3/8/8/8/8/8/8/4K3 w - - 0 0"),
+            FenError::new(FenField::FullmoveNumber, FenErrorReason::InvalidNumber)
+        );
+    }
+
+    #[test]
+    fn lenient_epd() {
+        generate_lookup_tables();
+
+        let position = Position::from_fen(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; id \"start\";",
+            FenMode::Lenient,
+        )
+        .unwrap();
+        assert_eq!(position.to_string(), Position::STARTING_FEN);
+
+        let position =
+            Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 12 40", FenMode::Lenient).unwrap();
+        assert_eq!(position.halfmove_clock, 12);
+        assert_eq!(position.fullmove_number, 40);
+
+        assert!(Position::from_fen("4k3/8/8/8/8/8/8/4K3 b", FenMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn display_error() {
+        let error = strict_error("4k3/8/8/3X4/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(
+            error.to_string(),
+            "invalid board: invalid character 'X' on d5"
+        );
+    }
+}
@@ -1,13 +1,20 @@
-use crate::{Color, Move, MoveList, Position};
-
-use self::game_result::GameResult;
+use crate::{Color, Error, Move, MoveList, Position};
 
 mod game_action;
 mod game_result;
 
+pub use game_action::*;
+pub use game_result::*;
+
 pub struct Game {
     position: Position,
     game_result: GameResult,
+    /// The positions before each move, which are used to detect repetitions.
+    history: Vec<Position>,
+    /// The moves played so far.
+    moves: Vec<Move>,
+    /// The color that offered a draw which wasn't answered yet.
+    draw_offer: Option<Color>,
 }
 
 impl Game {
@@ -15,9 +22,23 @@ impl Game {
         Game {
             position: Position::default(),
             game_result: GameResult::Ongoing,
+            history: Vec::new(),
+            moves: Vec::new(),
+            draw_offer: None,
         }
     }
 
+    /// Creates a game that starts from the given position instead of the starting position.
+    pub fn from_position(position: Position) -> Game {
+        let mut game = Game {
+            position,
+            ..Game::new()
+        };
+        game.update_result();
+
+        game
+    }
+
     pub fn color_to_move(&self) -> Color {
         self.position.color_to_move
     }
@@ -26,18 +47,126 @@ impl Game {
         self.position.legal_moves()
     }
 
+    /// Plays a move for the color to move. Moves after the game has ended are ignored.
+    /// A pending draw offer of the opponent is declined by making a move.
     pub fn make_move(&mut self, chess_move: Move) {
         if self.game_result == GameResult::Ongoing {
+            self.history.push(self.position);
+            self.moves.push(chess_move);
+
+            if self.draw_offer == Some(!self.position.color_to_move) {
+                self.draw_offer = None;
+            }
+
             self.position.make_move(chess_move);
+            self.update_result();
+        }
+    }
+
+    /// Performs an action of a player. Returns an error if the action is not allowed, for example
+    /// a move out of turn or accepting a draw that wasn't offered.
+    pub fn apply(&mut self, color: Color, action: GameAction) -> Result<(), Error> {
+        if self.game_result != GameResult::Ongoing {
+            return Err(Error::GameOver);
+        }
+
+        match action {
+            GameAction::Move(chess_move) => {
+                if color != self.color_to_move() || !self.moves().contains(&chess_move) {
+                    return Err(Error::InvalidMove);
+                }
 
-            if self.position().is_checkmate() {
-                self.game_result = GameResult::Win(!self.position.color_to_move);
-            } else if self.position().is_stalemate() {
-                self.game_result = GameResult::Draw;
+                self.make_move(chess_move);
             }
+            GameAction::Resign => self.game_result = GameResult::Win(!color),
+            GameAction::OfferDraw => {
+                // Offering a draw to a player who offered one as well is an agreement.
+                if self.draw_offer == Some(!color) {
+                    self.game_result = GameResult::Draw(DrawReason::Agreement);
+                } else {
+                    self.draw_offer = Some(color);
+                }
+            }
+            GameAction::AcceptDraw => {
+                if self.draw_offer != Some(!color) {
+                    return Err(Error::InvalidGameAction);
+                }
+
+                self.game_result = GameResult::Draw(DrawReason::Agreement);
+            }
+            GameAction::DeclineDraw => {
+                if self.draw_offer != Some(!color) {
+                    return Err(Error::InvalidGameAction);
+                }
+
+                self.draw_offer = None;
+            }
+            GameAction::ClaimDraw => {
+                let reason = self.claimable_draw().ok_or(Error::InvalidGameAction)?;
+                self.game_result = GameResult::Draw(reason);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends the game in a draw that wasn't reached by the players, for example because an engine
+    /// match adjudicated it.
+    pub fn adjudicate_draw(&mut self) -> Result<(), Error> {
+        if self.game_result != GameResult::Ongoing {
+            return Err(Error::GameOver);
+        }
+
+        self.game_result = GameResult::Draw(DrawReason::Adjudication);
+
+        Ok(())
+    }
+
+    /// Returns the reason a player could claim a draw in the current position, if there is one.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.position.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
         }
     }
 
+    /// Counts how often the current position occurred in this game, including the current one.
+    pub fn repetitions(&self) -> usize {
+        // A position can't repeat across a capture or pawn move. The zobrist keys can't be used
+        // for this, as they include en passant squares on which no capture is possible.
+        let reversible_moves = self.position.halfmove_clock as usize;
+
+        1 + self
+            .history
+            .iter()
+            .rev()
+            .take(reversible_moves)
+            .filter(|position| position.is_repetition_of(&self.position))
+            .count()
+    }
+
+    /// Ends the game if the last move caused a checkmate or one of the automatic draws.
+    fn update_result(&mut self) {
+        let position = &self.position;
+
+        self.game_result = if position.is_checkmate() {
+            GameResult::Win(!position.color_to_move)
+        } else if position.is_stalemate() {
+            GameResult::Draw(DrawReason::Stalemate)
+        } else if position.is_insufficient_material() {
+            GameResult::Draw(DrawReason::InsufficientMaterial)
+        } else if self.repetitions() >= 5 {
+            GameResult::Draw(DrawReason::FivefoldRepetition)
+        } else if position.halfmove_clock >= 150 {
+            GameResult::Draw(DrawReason::SeventyFiveMoveRule)
+        } else {
+            GameResult::Ongoing
+        };
+    }
+
     pub fn result(&self) -> GameResult {
         self.game_result
     }
@@ -45,6 +174,21 @@ impl Game {
     pub fn position(&self) -> &Position {
         &self.position
     }
+
+    /// Returns the position the game started from.
+    pub fn starting_position(&self) -> &Position {
+        self.history.first().unwrap_or(&self.position)
+    }
+
+    /// Returns all moves played so far.
+    pub fn move_history(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Returns the color that offered a draw the opponent didn't answer yet.
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
 }
 
 impl Default for Game {
@@ -52,3 +196,197 @@ impl Default for Game {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{LAN, generate_lookup_tables};
+
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mv in moves {
+            let lan = LAN::from_str(mv).unwrap();
+            let mv = Move::from_lan(&lan, game.position()).unwrap();
+            game.apply(game.color_to_move(), GameAction::Move(mv))
+                .unwrap();
+        }
+    }
+
+    const KNIGHT_SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    #[test]
+    fn threefold_repetition_claim() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        play(&mut game, &KNIGHT_SHUFFLE);
+        assert_eq!(game.claimable_draw(), None);
+        assert_eq!(
+            game.apply(Color::White, GameAction::ClaimDraw),
+            Err(Error::InvalidGameAction)
+        );
+
+        play(&mut game, &KNIGHT_SHUFFLE);
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        game.apply(Color::White, GameAction::ClaimDraw).unwrap();
+        assert_eq!(
+            game.result(),
+            GameResult::Draw(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn repetition_after_double_pawn_push() {
+        generate_lookup_tables();
+
+        // The position after 1.e4 has an en passant square, but no en passant capture.
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "e2e4", "g8f6", "g1f3", "f6g8", "f3g1", "g8f6", "g1f3", "f6g8", "f3g1",
+            ],
+        );
+
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        for _ in 0..4 {
+            play(&mut game, &KNIGHT_SHUFFLE);
+        }
+
+        assert_eq!(
+            game.result(),
+            GameResult::Draw(DrawReason::FivefoldRepetition)
+        );
+        assert_eq!(game.move_history().len(), 16);
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        generate_lookup_tables();
+
+        let mut game =
+            Game::from_position(Position::from_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 98 80").unwrap());
+
+        play(&mut game, &["a1a2"]);
+        assert_eq!(game.claimable_draw(), None);
+
+        play(&mut game, &["e8d8"]);
+        assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        generate_lookup_tables();
+
+        let mut game = Game::from_position(
+            Position::from_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 100").unwrap(),
+        );
+
+        play(&mut game, &["a1a2"]);
+
+        assert_eq!(
+            game.result(),
+            GameResult::Draw(DrawReason::SeventyFiveMoveRule)
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        generate_lookup_tables();
+
+        let mut game =
+            Game::from_position(Position::from_str("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1").unwrap());
+
+        play(&mut game, &["e1d2"]);
+
+        assert_eq!(
+            game.result(),
+            GameResult::Draw(DrawReason::InsufficientMaterial)
+        );
+    }
+
+    #[test]
+    fn resign() {
+        let mut game = Game::new();
+
+        game.apply(Color::White, GameAction::Resign).unwrap();
+
+        assert_eq!(game.result(), GameResult::Win(Color::Black));
+        assert_eq!(
+            game.apply(Color::Black, GameAction::OfferDraw),
+            Err(Error::GameOver)
+        );
+    }
+
+    #[test]
+    fn draw_offer() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        assert_eq!(
+            game.apply(Color::Black, GameAction::AcceptDraw),
+            Err(Error::InvalidGameAction)
+        );
+
+        game.apply(Color::White, GameAction::OfferDraw).unwrap();
+        assert_eq!(
+            game.apply(Color::White, GameAction::AcceptDraw),
+            Err(Error::InvalidGameAction)
+        );
+
+        game.apply(Color::Black, GameAction::DeclineDraw).unwrap();
+        assert_eq!(game.draw_offer(), None);
+
+        game.apply(Color::White, GameAction::OfferDraw).unwrap();
+        game.apply(Color::Black, GameAction::AcceptDraw).unwrap();
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Agreement));
+    }
+
+    #[test]
+    fn adjudicated_draw() {
+        let mut game = Game::new();
+
+        game.adjudicate_draw().unwrap();
+
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Adjudication));
+        assert_eq!(game.adjudicate_draw(), Err(Error::GameOver));
+    }
+
+    #[test]
+    fn move_declines_draw_offer() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        play(&mut game, &["e2e4"]);
+        game.apply(Color::White, GameAction::OfferDraw).unwrap();
+
+        play(&mut game, &["e7e5"]);
+
+        assert_eq!(game.draw_offer(), None);
+    }
+
+    #[test]
+    fn move_out_of_turn() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        let mv = game.moves()[0];
+
+        assert_eq!(
+            game.apply(Color::Black, GameAction::Move(mv)),
+            Err(Error::InvalidMove)
+        );
+    }
+}
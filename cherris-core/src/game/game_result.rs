@@ -3,24 +3,60 @@ use std::fmt::Display;
 use crate::Color;
 
 /// Represents the result of a chess game.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GameResult {
     Win(Color),
-    Draw,
+    Draw(DrawReason),
     Ongoing,
 }
 
+/// The reason a game ended in a draw.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawReason {
+    /// The color to move has no legal moves but is not in check.
+    Stalemate,
+    /// Both players agreed to a draw.
+    Agreement,
+    /// The same position occurred three times and a player claimed the draw.
+    ThreefoldRepetition,
+    /// The same position occurred five times.
+    FivefoldRepetition,
+    /// No capture or pawn move happened in the last 50 moves and a player claimed the draw.
+    FiftyMoveRule,
+    /// No capture or pawn move happened in the last 75 moves.
+    SeventyFiveMoveRule,
+    /// Neither player has enough material left to checkmate.
+    InsufficientMaterial,
+    /// Someone other than the players, like the arbiter of an engine match, declared the draw.
+    Adjudication,
+}
+
 impl Display for GameResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             GameResult::Win(Color::White) => write!(f, "1-0"),
             GameResult::Win(Color::Black) => write!(f, "0-1"),
-            GameResult::Draw => write!(f, "½-½"),
+            GameResult::Draw(_) => write!(f, "½-½"),
             GameResult::Ongoing => write!(f, "0-0"),
         }
     }
 }
 
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::Stalemate => write!(f, "stalemate"),
+            DrawReason::Agreement => write!(f, "agreement"),
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "seventy-five-move rule"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+            DrawReason::Adjudication => write!(f, "adjudication"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -39,7 +75,7 @@ mod tests {
 
     #[test]
     fn display_win_draw() {
-        let result = GameResult::Draw.to_string();
+        let result = GameResult::Draw(DrawReason::Stalemate).to_string();
         assert_eq!(result, "½-½");
     }
 
@@ -48,4 +84,10 @@ mod tests {
         let result = GameResult::Ongoing.to_string();
         assert_eq!(result, "0-0");
     }
+
+    #[test]
+    fn display_draw_reason() {
+        let reason = DrawReason::ThreefoldRepetition.to_string();
+        assert_eq!(reason, "threefold repetition");
+    }
 }
@@ -31,6 +31,12 @@ pub use crate::castling_rights::*;
 mod position;
 pub use crate::position::*;
 
+mod fen;
+pub use crate::fen::*;
+
+mod see;
+pub use crate::see::*;
+
 mod chess_move;
 pub use crate::chess_move::*;
 
@@ -58,4 +64,6 @@ pub use crate::zobrist::*;
 mod move_list;
 pub use crate::move_list::*;
 
+pub mod pgn;
+
 pub mod uci;
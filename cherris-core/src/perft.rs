@@ -1,6 +1,12 @@
 use crate::{MoveList, Position, generate_moves};
 
+/// Counts the leaf nodes of the move tree of `position` at `depth` plies.
 pub fn perft(depth: u64, position: &Position) -> usize {
+    let mut position = *position;
+    perft_make_unmake(depth, &mut position)
+}
+
+fn perft_make_unmake(depth: u64, position: &mut Position) -> usize {
     let mut nodes = 0;
     let mut moves = MoveList::new();
     generate_moves(position, &mut moves);
@@ -9,9 +15,9 @@ pub fn perft(depth: u64, position: &Position) -> usize {
         moves.len()
     } else {
         for mv in moves {
-            let mut next_position = *position;
-            next_position.make_move(mv);
-            nodes += perft(depth - 1, &next_position);
+            let undo_info = position.make_move(mv);
+            nodes += perft_make_unmake(depth - 1, position);
+            position.unmake_move(mv, undo_info);
         }
 
         nodes
@@ -21,14 +27,14 @@ pub fn perft(depth: u64, position: &Position) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::generate_lookup_tables;
+    use crate::{FenMode, generate_lookup_tables};
     use std::str::FromStr;
 
     fn test_edp(edp: &str) {
         generate_lookup_tables();
         let parts: Vec<&str> = edp.split(';').collect();
         let fen = parts[0];
-        let position = Position::from_str(fen).unwrap();
+        let position = Position::from_fen(fen, FenMode::Lenient).unwrap();
 
         for depth in parts.iter().skip(1) {
             let parts: Vec<&str> = depth.split(' ').collect();
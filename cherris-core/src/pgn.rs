@@ -0,0 +1,120 @@
+use std::fmt::Display;
+
+use crate::{Color, Game, GameResult, uci::UCIScore};
+
+mod pgn_reader;
+mod pgn_writer;
+
+pub use pgn_reader::*;
+
+/// The tags every PGN has to contain, in the order they are written.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A game together with the tags and comments of its PGN.
+pub struct Pgn {
+    /// The tag pairs in the order they were read or set.
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    /// The comment after each move, indexed by the ply of the move.
+    pub comments: Vec<Option<String>>,
+}
+
+impl Pgn {
+    pub fn new(game: Game) -> Pgn {
+        Pgn {
+            tags: Vec::new(),
+            game,
+            comments: Vec::new(),
+        }
+    }
+
+    /// Returns the value of a tag. Tag names are case sensitive.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, replacing the old value if the tag already exists.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Sets the comment written after the move with the given ply, starting at 0.
+    pub fn set_comment(&mut self, ply: usize, comment: &str) {
+        if self.comments.len() <= ply {
+            self.comments.resize(ply + 1, None);
+        }
+
+        self.comments[ply] = Some(comment.to_string());
+    }
+
+    /// Sets the comment after a move to an engine evaluation from the point of view of the color
+    /// that made the move, like `+0.35/12` or `-M3/20`.
+    pub fn set_eval(&mut self, ply: usize, score: &UCIScore, depth: u8) {
+        let eval = match score {
+            UCIScore::Centipawns(centipawns) => format!("{:+.2}", *centipawns as f64 / 100.0),
+            UCIScore::Mate(mate) if *mate >= 0 => format!("+M{}", mate),
+            UCIScore::Mate(mate) => format!("-M{}", -mate),
+        };
+
+        self.set_comment(ply, &format!("{}/{}", eval, depth));
+    }
+}
+
+/// Returns the result of a game as it is written in a PGN.
+pub fn pgn_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Win(Color::White) => "1-0",
+        GameResult::Win(Color::Black) => "0-1",
+        GameResult::Draw(_) => "1/2-1/2",
+        GameResult::Ongoing => "*",
+    }
+}
+
+/// The errors that can occur while reading a PGN.
+#[derive(Debug, PartialEq)]
+pub enum PgnError {
+    /// Reading from the source failed.
+    Io(String),
+    /// A tag pair on the given line is malformed.
+    InvalidTag { line: usize },
+    /// The FEN tag doesn't contain a valid position.
+    InvalidFen(String),
+    /// A move is not legal or can't be read as SAN.
+    IllegalMove {
+        move_number: usize,
+        san: String,
+        fen: String,
+    },
+    /// A `{` comment is never closed.
+    UnterminatedComment,
+    /// The parentheses of the variations don't match.
+    UnbalancedVariation,
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Io(err) => write!(f, "failed to read pgn: {}", err),
+            PgnError::InvalidTag { line } => write!(f, "invalid tag on line {}", line),
+            PgnError::InvalidFen(fen) => write!(f, "invalid fen {}", fen),
+            PgnError::IllegalMove {
+                move_number,
+                san,
+                fen,
+            } => write!(
+                f,
+                "illegal move {} at move {} in position {}",
+                san, move_number, fen
+            ),
+            PgnError::UnterminatedComment => write!(f, "unterminated comment"),
+            PgnError::UnbalancedVariation => write!(f, "unbalanced variation"),
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    io::{BufRead, Lines},
+    str::FromStr,
+};
+
+use crate::{
+    Color, Game, GameAction, GameResult, Move, Position,
+    pgn::{Pgn, PgnError},
+};
+
+/// Reads the games of a PGN one after another, so even large files never have to be kept in
+/// memory as a whole.
+pub struct PgnReader<R: BufRead> {
+    lines: Lines<R>,
+    line_number: usize,
+    /// A line that was read but belongs to the next game.
+    pending_line: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            line_number: 0,
+            pending_line: None,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, PgnError>> {
+        if let Some(line) = self.pending_line.take() {
+            return Some(Ok(line));
+        }
+
+        self.line_number += 1;
+        self.lines
+            .next()
+            .map(|line| line.map_err(|err| PgnError::Io(err.to_string())))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Pgn, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        let mut in_comment = false;
+
+        while let Some(line) = self.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            let trimmed = line.trim();
+
+            // Lines starting with % are escaped and ignored.
+            if trimmed.starts_with('%') && !in_comment {
+                continue;
+            }
+
+            if trimmed.starts_with('[') && !in_comment {
+                // A tag after the movetext belongs to the next game.
+                if !movetext.trim().is_empty() {
+                    self.pending_line = Some(line);
+                    break;
+                }
+
+                match parse_tag(trimmed) {
+                    Some(tag) => tags.push(tag),
+                    None => {
+                        return Some(Err(PgnError::InvalidTag {
+                            line: self.line_number,
+                        }));
+                    }
+                }
+                continue;
+            }
+
+            in_comment = ends_in_comment(trimmed, in_comment);
+            movetext.push_str(&line);
+            movetext.push('\n');
+        }
+
+        if tags.is_empty() && movetext.trim().is_empty() {
+            return None;
+        }
+
+        Some(parse_game(tags, &movetext))
+    }
+}
+
+/// Parses a tag pair like `[Event "F/S Return Match"]`.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let content = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = content.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unescaped.push(chars.next()?),
+            char => unescaped.push(char),
+        }
+    }
+
+    Some((name.to_string(), unescaped))
+}
+
+/// Checks if a line of movetext ends inside a `{` comment.
+fn ends_in_comment(line: &str, mut in_comment: bool) -> bool {
+    for char in line.chars() {
+        match char {
+            '{' if !in_comment => in_comment = true,
+            '}' if in_comment => in_comment = false,
+            ';' if !in_comment => return false,
+            _ => {}
+        }
+    }
+
+    in_comment
+}
+
+fn parse_game(tags: Vec<(String, String)>, movetext: &str) -> Result<Pgn, PgnError> {
+    let fen = tags
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map(|(_, fen)| fen.clone());
+    let position = match fen {
+        Some(fen) => Position::from_str(&fen).map_err(|_| PgnError::InvalidFen(fen))?,
+        None => Position::default(),
+    };
+
+    let mut pgn = Pgn::new(Game::from_position(position));
+    pgn.tags = tags;
+
+    let mut result = None;
+    let mut variation_depth = 0;
+    let mut chars = movetext.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => comment.push(char),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                add_comment(&mut pgn, variation_depth, &comment);
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|char| *char != '\n').collect();
+                add_comment(&mut pgn, variation_depth, &comment);
+            }
+            '(' => variation_depth += 1,
+            ')' => {
+                if variation_depth == 0 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                variation_depth -= 1;
+            }
+            // Numeric annotation glyphs like $1 are skipped.
+            '$' => while chars.next_if(|char| char.is_ascii_digit()).is_some() {},
+            char if char.is_whitespace() => {}
+            char => {
+                let mut token = char.to_string();
+                while let Some(char) =
+                    chars.next_if(|char| !char.is_whitespace() && !"{}();$".contains(*char))
+                {
+                    token.push(char);
+                }
+
+                if variation_depth == 0 {
+                    parse_token(&mut pgn, &token, &mut result)?;
+                }
+            }
+        }
+    }
+
+    if variation_depth != 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+
+    if let Some(result) = result {
+        apply_result(&mut pgn.game, &result);
+    }
+
+    Ok(pgn)
+}
+
+/// Handles a token of the main line, which is a move number, a move or the result.
+fn parse_token(pgn: &mut Pgn, token: &str, result: &mut Option<String>) -> Result<(), PgnError> {
+    if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        *result = Some(token.to_string());
+        return Ok(());
+    }
+
+    // Move numbers can be written without a space in front of the move, like 1.e4.
+    let san = token.trim_start_matches(|char: char| char.is_ascii_digit() || char == '.');
+    if san.is_empty() || san.chars().all(|char| char == '!' || char == '?') {
+        return Ok(());
+    }
+
+    let position = *pgn.game.position();
+    let chess_move = Move::from_san(san, &position)
+        .ok()
+        .filter(|_| pgn.game.result() == GameResult::Ongoing)
+        .ok_or_else(|| PgnError::IllegalMove {
+            move_number: position.fullmove_number,
+            san: san.to_string(),
+            fen: position.to_string(),
+        })?;
+
+    pgn.game.make_move(chess_move);
+
+    Ok(())
+}
+
+/// Adds a comment of the main line to the last move. Comments before the first move and inside
+/// variations are dropped.
+fn add_comment(pgn: &mut Pgn, variation_depth: usize, comment: &str) {
+    let plies = pgn.game.move_history().len();
+    if variation_depth == 0 && plies > 0 {
+        pgn.set_comment(plies - 1, comment.trim());
+    }
+}
+
+/// Ends a game that is still ongoing with the result of the PGN. A game that isn't finished on
+/// the board was either resigned or drawn by agreement or claim.
+fn apply_result(game: &mut Game, result: &str) {
+    if game.result() != GameResult::Ongoing {
+        return;
+    }
+
+    let color_to_move = game.color_to_move();
+    let actions = match result {
+        "1-0" => vec![(Color::Black, GameAction::Resign)],
+        "0-1" => vec![(Color::White, GameAction::Resign)],
+        "1/2-1/2" if game.claimable_draw().is_some() => {
+            vec![(color_to_move, GameAction::ClaimDraw)]
+        }
+        "1/2-1/2" => vec![
+            (color_to_move, GameAction::OfferDraw),
+            (!color_to_move, GameAction::AcceptDraw),
+        ],
+        _ => vec![],
+    };
+
+    for (color, action) in actions {
+        game.apply(color, action)
+            .expect("Ending an ongoing game is always allowed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{DrawReason, generate_lookup_tables};
+
+    use super::*;
+
+    const PGN: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+% This line is ignored.
+[Event "Variations"]
+[Result "1-0"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"]
+
+1. Ra7 $1 (1. Ra8+ {a comment with ( and )} Kd7 (1... Ke7)) Kf8 ; the king runs
+2. Kf2 Kg8 1-0
+"#;
+
+    #[test]
+    fn read_games() {
+        generate_lookup_tables();
+
+        let games: Vec<Pgn> = PgnReader::new(Cursor::new(PGN))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(games.len(), 2);
+
+        let fischer = &games[0];
+        assert_eq!(fischer.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(fischer.game.move_history().len(), 85);
+        assert_eq!(
+            fischer.comments[4].as_deref(),
+            Some("This opening is called the Ruy Lopez.")
+        );
+        assert_eq!(
+            fischer.game.result(),
+            GameResult::Draw(DrawReason::Agreement)
+        );
+
+        let variations = &games[1];
+        assert_eq!(variations.game.move_history().len(), 4);
+        assert_eq!(variations.comments[1].as_deref(), Some("the king runs"));
+        assert_eq!(variations.game.result(), GameResult::Win(Color::White));
+        assert_eq!(
+            variations.game.starting_position().to_string(),
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn illegal_move() {
+        generate_lookup_tables();
+
+        let pgn = "[Event \"?\"]\n\n1. e4 e5 2. Ke3 *\n";
+        let result = PgnReader::new(Cursor::new(pgn)).next().unwrap();
+
+        assert_eq!(
+            result.err(),
+            Some(PgnError::IllegalMove {
+                move_number: 2,
+                san: "Ke3".to_string(),
+                fen: "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn unbalanced_variation() {
+        generate_lookup_tables();
+
+        let pgn = "1. e4 (1. d4 *\n";
+        let result = PgnReader::new(Cursor::new(pgn)).next().unwrap();
+
+        assert_eq!(result.err(), Some(PgnError::UnbalancedVariation));
+    }
+
+    #[test]
+    fn invalid_tag() {
+        let pgn = "[Event \"?\"]\n[Site]\n\n*\n";
+        let result = PgnReader::new(Cursor::new(pgn)).next().unwrap();
+
+        assert_eq!(result.err(), Some(PgnError::InvalidTag { line: 2 }));
+    }
+
+    #[test]
+    fn write_and_read() {
+        generate_lookup_tables();
+
+        let mut games = PgnReader::new(Cursor::new(PGN));
+        let pgn = games.next().unwrap().unwrap();
+        let written = pgn.to_string();
+
+        let read = PgnReader::new(Cursor::new(written.as_str()))
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read.game.move_history(), pgn.game.move_history());
+        assert_eq!(read.to_string(), written);
+    }
+}
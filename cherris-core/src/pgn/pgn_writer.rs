@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+use crate::{
+    Color, Position,
+    pgn::{Pgn, SEVEN_TAG_ROSTER, pgn_result},
+};
+
+/// The maximum length of a line of movetext.
+const LINE_LENGTH: usize = 79;
+
+impl Display for Pgn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = pgn_result(self.game.result());
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => result,
+                "Date" => self.tag(name).unwrap_or("????.??.??"),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            write_tag(f, name, value)?;
+        }
+
+        let starting_position = self.game.starting_position();
+        let fen = starting_position.to_string();
+        if fen != Position::STARTING_FEN {
+            write_tag(f, "SetUp", "1")?;
+            write_tag(f, "FEN", &fen)?;
+        }
+
+        for (name, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+                write_tag(f, name, value)?;
+            }
+        }
+
+        writeln!(f)?;
+
+        let mut words = Vec::new();
+        let mut position = *starting_position;
+        let mut needs_move_number = true;
+        for (ply, chess_move) in self.game.move_history().iter().enumerate() {
+            match position.color_to_move {
+                Color::White => words.push(format!("{}.", position.fullmove_number)),
+                Color::Black if needs_move_number => {
+                    words.push(format!("{}...", position.fullmove_number))
+                }
+                Color::Black => {}
+            }
+            words.push(chess_move.to_san(&position));
+            needs_move_number = false;
+
+            if let Some(Some(comment)) = self.comments.get(ply) {
+                let comment = format!("{{{}}}", comment.replace('}', ""));
+                words.extend(comment.split_whitespace().map(|word| word.to_string()));
+                needs_move_number = true;
+            }
+
+            position.make_move(*chess_move);
+        }
+        words.push(result.to_string());
+
+        let mut line_length = 0;
+        for word in words {
+            if line_length > 0 && line_length + 1 + word.len() > LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", word)?;
+            line_length += word.len();
+        }
+
+        writeln!(f)
+    }
+}
+
+fn write_tag(f: &mut std::fmt::Formatter<'_>, name: &str, value: &str) -> std::fmt::Result {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    writeln!(f, "[{} \"{}\"]", name, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Game, GameAction, LAN, Move, generate_lookup_tables, uci::UCIScore};
+
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mv in moves {
+            let lan = LAN::from_str(mv).unwrap();
+            game.make_move(Move::from_lan(&lan, game.position()).unwrap());
+        }
+    }
+
+    #[test]
+    fn seven_tag_roster() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        let mut pgn = Pgn::new(game);
+        pgn.set_tag("White", "cherris");
+        pgn.set_tag("Annotator", "Quote \"me\"");
+
+        assert_eq!(
+            pgn.to_string(),
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"cherris\"]\n\
+             [Black \"?\"]\n\
+             [Result \"0-1\"]\n\
+             [Annotator \"Quote \\\"me\\\"\"]\n\
+             \n\
+             1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+    }
+
+    #[test]
+    fn comments_and_evals() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "e7e5", "g1f3"]);
+        let mut pgn = Pgn::new(game);
+        pgn.set_comment(0, "best by test");
+        pgn.set_eval(1, &UCIScore::Centipawns(-35), 12);
+        pgn.set_eval(2, &UCIScore::Mate(3), 20);
+
+        assert!(
+            pgn.to_string()
+                .ends_with("1. e4 {best by test} 1... e5 {-0.35/12} 2. Nf3 {+M3/20} *\n")
+        );
+    }
+
+    #[test]
+    fn fen_tag() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("4k3/8/8/8/8/8/8/R3K3 b - - 0 30").unwrap();
+        let mut game = Game::from_position(position);
+        play(&mut game, &["e8d7"]);
+        game.apply(Color::White, GameAction::Resign).unwrap();
+
+        let pgn = Pgn::new(game).to_string();
+
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 30\"]\n"));
+        assert!(pgn.ends_with("\n30... Kd7 0-1\n"));
+    }
+
+    #[test]
+    fn wrap_lines() {
+        generate_lookup_tables();
+
+        let mut game = Game::new();
+        for _ in 0..4 {
+            play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        }
+
+        let pgn = Pgn::new(game).to_string();
+
+        assert!(pgn.lines().all(|line| line.len() <= LINE_LENGTH));
+    }
+}
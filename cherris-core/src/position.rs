@@ -1,10 +1,24 @@
 use std::{fmt::Display, str::FromStr};
 
 use crate::{
-    Bitboard, Board, CastlingRights, Color, Error, File, Move, MoveList, Piece, Rank, Role, Square,
-    Zobrist, generate_moves,
+    Bitboard, Board, CastlingRights, Color, Error, FenMode, File, Move, MoveList, Piece, Rank,
+    Role, Square, Zobrist, generate_moves,
 };
 
+/// The state of a `Position` that can't be restored from a move alone. It is returned by
+/// `Position::make_move` and needed to take the move back with `Position::unmake_move`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct UndoInfo {
+    /// The piece captured by the move, if any.
+    pub capture: Option<Piece>,
+    pub castling_rights: [CastlingRights; Color::COUNT],
+    pub en_passant_square: Option<Square>,
+    pub halfmove_clock: u8,
+    pub zobrist: Zobrist,
+    pub pawn_key: Zobrist,
+    pub material_key: Zobrist,
+}
+
 /// Represents a chess position.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Position {
@@ -15,6 +29,10 @@ pub struct Position {
     pub fullmove_number: usize,
     pub castling_rights: [CastlingRights; Color::COUNT],
     pub zobrist: Zobrist,
+    /// The Zobrist key of only the pawns.
+    pub pawn_key: Zobrist,
+    /// The Zobrist key of the number of pieces of each color and role.
+    pub material_key: Zobrist,
 }
 
 impl Position {
@@ -25,9 +43,32 @@ impl Position {
         Position::from_str(Position::STARTING_FEN).unwrap()
     }
 
-    pub fn make_move(&mut self, chess_move: Move) {
+    /// Makes a move and returns the information needed to take it back with `unmake_move`.
+    pub fn make_move(&mut self, chess_move: Move) -> UndoInfo {
+        let capture = match chess_move {
+            Move::Standard { capture, .. } => capture,
+            Move::EnPassant { .. } => Some(Role::Pawn),
+            Move::CastleShort | Move::CastleLong => None,
+        };
+        let undo_info = UndoInfo {
+            capture: capture.map(|role| Piece {
+                color: !self.color_to_move,
+                role,
+            }),
+            castling_rights: self.castling_rights,
+            en_passant_square: self.en_passant_square,
+            halfmove_clock: self.halfmove_clock,
+            zobrist: self.zobrist,
+            pawn_key: self.pawn_key,
+            material_key: self.material_key,
+        };
+
+        self.material_key
+            .update_material(chess_move, self.color_to_move, &self.board);
+        self.pawn_key.update_pawns(chess_move, self.color_to_move);
         self.board.make_move(self.color_to_move, chess_move);
         self.zobrist.update_castling_right(self.castling_rights);
+        self.zobrist.update_en_passant(self.en_passant_square);
 
         self.en_passant_square = match chess_move {
             Move::Standard {
@@ -40,11 +81,12 @@ impl Position {
             self.fullmove_number += 1;
         }
 
-        if let Move::Standard { role, capture, .. } = chess_move {
-            if role != Role::Pawn && capture.is_some() {
-                self.halfmove_clock += 1;
-            }
-        }
+        // The halfmove clock counts the moves since the last capture or pawn move.
+        self.halfmove_clock = match chess_move {
+            Move::Standard { role, capture, .. } if role == Role::Pawn || capture.is_some() => 0,
+            Move::EnPassant { .. } => 0,
+            _ => self.halfmove_clock.saturating_add(1),
+        };
 
         self.castling_rights[self.color_to_move] = match chess_move {
             Move::Standard { from, role, .. } => {
@@ -83,29 +125,27 @@ impl Position {
 
         if let Move::Standard {
             to,
-            capture: Some(role),
+            capture: Some(Role::Rook),
             ..
         } = chess_move
         {
-            if role == Role::Rook {
-                match !self.color_to_move {
-                    Color::White => {
-                        if to == Square::A1 {
-                            self.castling_rights[!self.color_to_move].remove_queen_side();
-                        }
+            match !self.color_to_move {
+                Color::White => {
+                    if to == Square::A1 {
+                        self.castling_rights[!self.color_to_move].remove_queen_side();
+                    }
 
-                        if to == Square::H1 {
-                            self.castling_rights[!self.color_to_move].remove_king_side();
-                        }
+                    if to == Square::H1 {
+                        self.castling_rights[!self.color_to_move].remove_king_side();
+                    }
+                }
+                Color::Black => {
+                    if to == Square::A8 {
+                        self.castling_rights[!self.color_to_move].remove_queen_side();
                     }
-                    Color::Black => {
-                        if to == Square::A8 {
-                            self.castling_rights[!self.color_to_move].remove_queen_side();
-                        }
 
-                        if to == Square::H8 {
-                            self.castling_rights[!self.color_to_move].remove_king_side();
-                        }
+                    if to == Square::H8 {
+                        self.castling_rights[!self.color_to_move].remove_king_side();
                     }
                 }
             }
@@ -114,6 +154,82 @@ impl Position {
         self.zobrist.update_castling_right(self.castling_rights);
         self.zobrist.update(chess_move, self.color_to_move);
         self.color_to_move = !self.color_to_move;
+
+        debug_assert_eq!(
+            self.zobrist,
+            Zobrist::from_position(self),
+            "incremental zobrist key differs from the recomputed key after {:?}",
+            chess_move
+        );
+        debug_assert_eq!(self.pawn_key, Zobrist::pawns_from_position(self));
+        debug_assert_eq!(self.material_key, Zobrist::material_from_position(self));
+
+        undo_info
+    }
+
+    /// Takes back the last move made with `make_move`, which returned `undo_info`.
+    pub fn unmake_move(&mut self, chess_move: Move, undo_info: UndoInfo) {
+        self.color_to_move = !self.color_to_move;
+
+        // All changes a move makes to the board are reversible, so making it again undoes it.
+        self.board.make_move(self.color_to_move, chess_move);
+
+        if self.color_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        self.castling_rights = undo_info.castling_rights;
+        self.en_passant_square = undo_info.en_passant_square;
+        self.halfmove_clock = undo_info.halfmove_clock;
+        self.zobrist = undo_info.zobrist;
+        self.pawn_key = undo_info.pawn_key;
+        self.material_key = undo_info.material_key;
+    }
+
+    /// Passes the turn to the other color without moving a piece. This is not a legal chess move
+    /// and is only used by the search, so the color to move must not be in check.
+    pub fn make_null_move(&mut self) -> UndoInfo {
+        let undo_info = UndoInfo {
+            capture: None,
+            castling_rights: self.castling_rights,
+            en_passant_square: self.en_passant_square,
+            halfmove_clock: self.halfmove_clock,
+            zobrist: self.zobrist,
+            pawn_key: self.pawn_key,
+            material_key: self.material_key,
+        };
+
+        self.zobrist.update_en_passant(self.en_passant_square);
+        self.zobrist.update_color_to_move();
+        self.en_passant_square = None;
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        if self.color_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.color_to_move = !self.color_to_move;
+
+        debug_assert_eq!(self.zobrist, Zobrist::from_position(self));
+
+        undo_info
+    }
+
+    /// Takes back a null move made with `make_null_move`, which returned `undo_info`.
+    pub fn unmake_null_move(&mut self, undo_info: UndoInfo) {
+        self.color_to_move = !self.color_to_move;
+        if self.color_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        self.en_passant_square = undo_info.en_passant_square;
+        self.halfmove_clock = undo_info.halfmove_clock;
+        self.zobrist = undo_info.zobrist;
+    }
+
+    /// Checks if the color to move has any pieces besides pawns and its king.
+    pub fn has_non_pawn_material(&self) -> bool {
+        let pawns_and_king = self.board.role[Role::Pawn] | self.board.role[Role::King];
+
+        !(self.board.color[self.color_to_move] & !pawns_and_king).is_empty()
     }
 
     pub fn legal_moves(&self) -> MoveList {
@@ -145,87 +261,59 @@ impl Position {
 
         moves.is_empty() && !check
     }
-}
-
-impl FromStr for Position {
-    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split(' ').collect();
-        let pieces_str = parts[0];
-        let color_to_move = parts[1];
-        let castling_rights = parts[2];
-        let en_passant = parts[3];
-        let halfmove_clock = parts[4];
-        let fullmove_number = parts[5];
-
-        let mut file = File::A;
-        let mut rank = Rank::Eigth;
-        let mut role = [Bitboard::EMPTY; Role::COUNT];
-        let mut color = [Bitboard::EMPTY; Color::COUNT];
-
-        for char in pieces_str.chars() {
-            let sqaure = Square::from((file, rank));
-
-            match char {
-                '/' => {
-                    rank = rank.down();
-                    file = File::A;
-                    continue;
-                }
-                '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' => {
-                    for _ in 0..char.to_digit(10).unwrap() {
-                        file = file.right();
-                    }
-                    continue;
-                }
-                c => {
-                    let piece = Piece::try_from(c)?;
-                    let square_bb = Bitboard::from(sqaure);
+    /// Checks if neither color has enough material left to checkmate, which is the case with
+    /// only kings, a king and a single minor piece against a king, or kings and bishops that all
+    /// stand on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let board = &self.board;
+        let heavy_pieces =
+            board.role[Role::Pawn] | board.role[Role::Rook] | board.role[Role::Queen];
+        if !heavy_pieces.is_empty() {
+            return false;
+        }
 
-                    role[piece.role] |= square_bb;
-                    color[piece.color] |= square_bb;
-                }
-            }
+        let knights = board.role[Role::Knight];
+        let bishops = board.role[Role::Bishop];
+        let minor_pieces = (knights | bishops).population_count();
 
-            file = file.right();
+        if minor_pieces <= 1 {
+            return true;
         }
 
-        let board = Board {
-            role,
-            color,
-            occupied: color[Color::White] | color[Color::Black],
-        };
-
-        let color_to_move = Color::from_str(color_to_move)?;
-        let castling_rights = CastlingRights::from_fen_str(castling_rights);
+        knights.is_empty()
+            && ((bishops & Bitboard::LIGHT_SQUARES).is_empty()
+                || (bishops & !Bitboard::LIGHT_SQUARES).is_empty())
+    }
 
-        let mut en_passant_square = None;
-        if en_passant != "-" {
-            let sqaure = Square::from_str(en_passant)?;
-            en_passant_square = Some(sqaure);
-        }
+    /// Checks if two positions are the same in the sense of the repetition rules: the same pieces
+    /// on the same squares, the same color to move and the same possible moves, which includes
+    /// castling rights and en passant captures.
+    pub fn is_repetition_of(&self, other: &Position) -> bool {
+        self.board == other.board
+            && self.color_to_move == other.color_to_move
+            && self.castling_rights == other.castling_rights
+            && self.en_passant_capture() == other.en_passant_capture()
+    }
 
-        let halfmove_clock = halfmove_clock
-            .parse::<u8>()
-            .map_err(|_| Error::InvalidFen)?;
-
-        let fullmove_number = fullmove_number
-            .parse::<usize>()
-            .map_err(|_| Error::InvalidFen)?;
-
-        Ok(Position {
-            board,
-            color_to_move,
-            en_passant_square,
-            halfmove_clock,
-            fullmove_number,
-            castling_rights,
-            zobrist: Zobrist::DEFAULT,
+    /// Returns the en passant square if an en passant capture is actually possible.
+    fn en_passant_capture(&self) -> Option<Square> {
+        self.en_passant_square.filter(|_| {
+            self.legal_moves()
+                .iter()
+                .any(|mv| matches!(mv, Move::EnPassant { .. }))
         })
     }
 }
 
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Position::from_fen(s, FenMode::Strict).map_err(Error::InvalidFen)
+    }
+}
+
 impl Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for rank in Rank::iter().rev() {
@@ -283,8 +371,111 @@ impl Default for Position {
 
 #[cfg(test)]
 mod tests {
+    use crate::{LAN, generate_lookup_tables};
+
     use super::*;
 
+    /// Makes and unmakes every legal move up to `depth` plies deep and checks that the position is
+    /// restored exactly.
+    fn assert_unmake_restores(position: &mut Position, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+
+        let before = *position;
+        for mv in position.legal_moves() {
+            let undo_info = position.make_move(mv);
+            assert_unmake_restores(position, depth - 1);
+            position.unmake_move(mv, undo_info);
+
+            assert_eq!(*position, before, "{} after {:?}", before, mv);
+        }
+    }
+
+    #[test]
+    fn unmake_move_restores_position() {
+        generate_lookup_tables();
+
+        for fen in [
+            Position::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let mut position = Position::from_str(fen).unwrap();
+            assert_unmake_restores(&mut position, 3);
+        }
+    }
+
+    #[test]
+    fn make_move_undo_info() {
+        generate_lookup_tables();
+
+        let mut position =
+            Position::from_str("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        let lan = LAN::from_str("e5f6").unwrap();
+        let mv = Move::from_lan(&lan, &position).unwrap();
+
+        let before = position;
+        let undo_info = position.make_move(mv);
+
+        assert_eq!(undo_info.capture, Some(Piece::BLACK_PAWN));
+        assert_eq!(undo_info.en_passant_square, Some(Square::F6));
+        assert_eq!(undo_info.halfmove_clock, 0);
+        assert_eq!(undo_info.zobrist, before.zobrist);
+
+        position.unmake_move(mv, undo_info);
+
+        assert_eq!(position, before);
+    }
+
+    #[test]
+    fn null_move() {
+        generate_lookup_tables();
+
+        let mut position =
+            Position::from_str("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        let before = position;
+
+        let undo_info = position.make_null_move();
+
+        assert_eq!(position.color_to_move, Color::Black);
+        assert_eq!(position.en_passant_square, None);
+        assert_eq!(position.zobrist, Zobrist::from_position(&position));
+        assert_ne!(position.zobrist, before.zobrist);
+
+        position.unmake_null_move(undo_info);
+
+        assert_eq!(position, before);
+    }
+
+    #[test]
+    fn null_move_keeps_halfmove_clock() {
+        generate_lookup_tables();
+
+        let mut position = Position::from_str("4k3/8/8/8/8/8/8/R3K3 w - - 12 40").unwrap();
+
+        let undo_info = position.make_null_move();
+        assert_eq!(position.halfmove_clock, 13);
+
+        position.unmake_null_move(undo_info);
+        assert_eq!(position.halfmove_clock, 12);
+    }
+
+    #[test]
+    fn non_pawn_material() {
+        let position = Position::from_str("4k3/4p3/8/8/8/8/4P3/4K1N1 w - - 0 1").unwrap();
+
+        assert!(position.has_non_pawn_material());
+
+        let position = Position::from_str("4k3/4p3/8/8/8/8/4P3/4K1N1 b - - 0 1").unwrap();
+
+        assert!(!position.has_non_pawn_material());
+    }
+
     #[test]
     fn is_in_check_true() {
         let pos =
@@ -296,7 +487,7 @@ mod tests {
     #[test]
     fn is_in_check_false() {
         let pos =
-            Position::from_str("rnbqkbnr/ppppp1pp/8/5p1Q/4P3/8/PPPP1PPP/RNB1KBNR w KQkq - 1 2")
+            Position::from_str("rnbqkbnr/ppppp2p/6p1/5p1Q/4P3/8/PPPP1PPP/RNB1KBNR w KQkq - 0 3")
                 .unwrap();
         assert!(!pos.is_in_check());
     }
@@ -328,6 +519,41 @@ mod tests {
         assert!(!pos.is_stalemate());
     }
 
+    #[test]
+    fn halfmove_clock() {
+        generate_lookup_tables();
+
+        let mut pos = Position::from_str("4k3/4p3/8/8/8/8/4P3/R3K3 w - - 10 20").unwrap();
+
+        pos.make_move(Move::from_lan(&LAN::from_str("a1a2").unwrap(), &pos).unwrap());
+        assert_eq!(pos.halfmove_clock, 11);
+
+        pos.make_move(Move::from_lan(&LAN::from_str("e7e5").unwrap(), &pos).unwrap());
+        assert_eq!(pos.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert!(Position::from_str(fen).unwrap().is_insufficient_material());
+        }
+
+        for fen in [
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+            "1n2k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert!(!Position::from_str(fen).unwrap().is_insufficient_material());
+        }
+    }
+
     #[test]
     fn display_starting_pos() {
         assert_eq!(Position::default().to_string(), Position::STARTING_FEN);
@@ -0,0 +1,230 @@
+use crate::{
+    Bitboard, Color, Move, Position, Role, Square, bishop_attacks, bishop_xray_attacks,
+    rook_attacks, rook_xray_attacks,
+};
+
+/// The values of the roles used by the static exchange evaluation.
+pub const SEE_VALUE: [i16; Role::COUNT] = [100, 300, 300, 500, 900, 20000];
+
+impl Position {
+    /// Statically evaluates the material balance of a move after all captures on its target
+    /// square, where both sides capture with their least valuable piece first and may stop
+    /// capturing when it would lose material. The result is from the view of the moving color.
+    pub fn see(&self, chess_move: Move) -> i16 {
+        let Some(exchange) = Exchange::new(self, chess_move) else {
+            return 0;
+        };
+
+        exchange.resolve()
+    }
+
+    /// Checks if the static exchange evaluation of a move is at least `threshold`.
+    pub fn see_ge(&self, chess_move: Move, threshold: i16) -> bool {
+        self.see(chess_move) >= threshold
+    }
+}
+
+/// The state of a capture sequence on a single square.
+struct Exchange<'a> {
+    position: &'a Position,
+    square: Square,
+    occupied: Bitboard,
+    /// The value of the first capture including promotions.
+    gain: i16,
+    /// The value of the piece standing on the square after the first move.
+    piece_value: i16,
+}
+
+impl<'a> Exchange<'a> {
+    fn new(position: &'a Position, chess_move: Move) -> Option<Exchange<'a>> {
+        let occupied = position.board.occupied;
+
+        match chess_move {
+            Move::Standard {
+                from,
+                to,
+                role,
+                capture,
+                promotion,
+                ..
+            } => {
+                let captured = capture.map_or(0, |role| SEE_VALUE[role]);
+                let (gain, piece_value) = match promotion {
+                    Some(promotion) => (
+                        captured + SEE_VALUE[promotion] - SEE_VALUE[Role::Pawn],
+                        SEE_VALUE[promotion],
+                    ),
+                    None => (captured, SEE_VALUE[role]),
+                };
+
+                Some(Exchange {
+                    position,
+                    square: to,
+                    occupied: occupied ^ Bitboard::from(from),
+                    gain,
+                    piece_value,
+                })
+            }
+            Move::EnPassant { from, to, target } => Some(Exchange {
+                position,
+                square: to,
+                occupied: occupied ^ Bitboard::from(from) ^ Bitboard::from(target),
+                gain: SEE_VALUE[Role::Pawn],
+                piece_value: SEE_VALUE[Role::Pawn],
+            }),
+            Move::CastleShort | Move::CastleLong => None,
+        }
+    }
+
+    fn resolve(mut self) -> i16 {
+        let board = &self.position.board;
+        let bishops_queens = board.role[Role::Bishop] | board.role[Role::Queen];
+        let rooks_queens = board.role[Role::Rook] | board.role[Role::Queen];
+
+        // Only sliders that can see the square through at most one piece can be revealed by the
+        // next capture, so the attacks of the square only have to be recomputed if there are any.
+        let xray_bishops = bishop_xray_attacks(self.square, self.occupied) & bishops_queens;
+        let xray_rooks = rook_xray_attacks(self.square, self.occupied) & rooks_queens;
+
+        let mut attackers = board.attackers_to(self.square, self.occupied);
+        let mut color = !self.position.color_to_move;
+        let mut gains = [0; 32];
+        gains[0] = self.gain;
+        let mut depth = 0;
+
+        while let Some((attacker, role)) = self.least_valuable_attacker(attackers, color) {
+            depth += 1;
+            gains[depth] = self.piece_value - gains[depth - 1];
+
+            // Neither side can improve on the result by continuing the exchange.
+            if gains[depth].max(-gains[depth - 1]) < 0 {
+                depth -= 1;
+                break;
+            }
+
+            self.occupied ^= Bitboard::from(attacker);
+            if matches!(role, Role::Pawn | Role::Bishop | Role::Queen) && !xray_bishops.is_empty() {
+                attackers |= bishop_attacks(self.square, self.occupied) & bishops_queens;
+            }
+            if matches!(role, Role::Rook | Role::Queen) && !xray_rooks.is_empty() {
+                attackers |= rook_attacks(self.square, self.occupied) & rooks_queens;
+            }
+            attackers &= self.occupied;
+
+            // The king can't capture while the square is still defended.
+            if role == Role::King && !(attackers & board.color[!color]).is_empty() {
+                depth -= 1;
+                break;
+            }
+
+            self.piece_value = SEE_VALUE[role];
+            color = !color;
+
+            if depth == gains.len() - 1 {
+                break;
+            }
+        }
+
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+
+        gains[0]
+    }
+
+    fn least_valuable_attacker(&self, attackers: Bitboard, color: Color) -> Option<(Square, Role)> {
+        let board = &self.position.board;
+        let attackers = attackers & board.color[color];
+
+        Role::ALL.into_iter().find_map(|role| {
+            (attackers & board.role[role])
+                .next()
+                .map(|square| (square, role))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{LAN, generate_lookup_tables};
+
+    use super::*;
+
+    fn see(fen: &str, lan: &str) -> i16 {
+        generate_lookup_tables();
+
+        let position = Position::from_str(fen).unwrap();
+        let lan = LAN::from_str(lan).unwrap();
+        let chess_move = Move::from_lan(&lan, &position).unwrap();
+
+        position.see(chess_move)
+    }
+
+    #[test]
+    fn see_undefended() {
+        assert_eq!(
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+            100
+        );
+    }
+
+    #[test]
+    fn see_defended() {
+        assert_eq!(
+            see(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5"
+            ),
+            -200
+        );
+    }
+
+    #[test]
+    fn see_xray() {
+        // The queen behind the rook joins the exchange once the rook captured.
+        assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/3QK3 w - - 0 1", "d2d5"), 100);
+        assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+        assert_eq!(see("3q2k1/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), -400);
+    }
+
+    #[test]
+    fn see_quiet_move_to_attacked_square() {
+        assert_eq!(see("4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1", "d1d5"), -900);
+        assert_eq!(see("4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1", "d1d2"), 0);
+    }
+
+    #[test]
+    fn see_promotion() {
+        assert_eq!(see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), 800);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), -100);
+    }
+
+    #[test]
+    fn see_king_cant_recapture_defended() {
+        assert_eq!(see("8/4k3/3p4/8/8/8/3R4/3RK3 w - - 0 1", "d2d6"), 100);
+        assert_eq!(see("8/4k3/3p4/8/8/8/3R4/4K3 w - - 0 1", "d2d6"), -400);
+    }
+
+    #[test]
+    fn see_en_passant() {
+        assert_eq!(
+            see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            SEE_VALUE[Role::Pawn]
+        );
+    }
+
+    #[test]
+    fn see_ge_threshold() {
+        generate_lookup_tables();
+
+        let position =
+            Position::from_str("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1").unwrap();
+        let chess_move = Move::from_lan(&LAN::from_str("e1e5").unwrap(), &position).unwrap();
+
+        assert!(position.see_ge(chess_move, 100));
+        assert!(!position.see_ge(chess_move, 101));
+    }
+}
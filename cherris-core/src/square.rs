@@ -1,6 +1,5 @@
 use std::ops::IndexMut;
 use std::str::FromStr;
-use std::usize;
 use std::{fmt::Display, ops::Index, slice::Iter};
 
 use crate::{Error, File, Rank};
@@ -2,8 +2,10 @@ mod option_params;
 mod position_params;
 mod register_params;
 mod uci_engine_command;
+mod uci_engine_process;
 mod uci_go_params;
 mod uci_gui_command;
+mod uci_move;
 mod uci_option;
 mod uci_search_info;
 
@@ -11,6 +13,7 @@ pub use option_params::*;
 pub use position_params::*;
 pub use register_params::*;
 pub use uci_engine_command::*;
+pub use uci_engine_process::*;
 pub use uci_go_params::*;
 pub use uci_gui_command::*;
 pub use uci_option::*;
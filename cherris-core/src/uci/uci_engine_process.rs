@@ -0,0 +1,181 @@
+use std::{
+    ffi::OsStr,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::uci::{UCIEngineCommand, UCIGuiCommand, UCIOption};
+
+/// How long the engine may take to answer `uci` and `isready`.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the engine may take to exit after `quit` before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A UCI engine that runs as a child process. Its output is read and parsed on its own thread,
+/// so reading never blocks unless asked to.
+pub struct UciEngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    output: Receiver<UCIGuiCommand>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<UCIOption>,
+}
+
+impl UciEngineProcess {
+    /// Starts the engine at `path`, reads its id and options and waits until it is ready.
+    pub fn spawn(path: impl AsRef<OsStr>) -> io::Result<UciEngineProcess> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                // Lines that aren't UCI, like debug output, are skipped.
+                let Ok(command) = UCIGuiCommand::from_str(&line) else {
+                    continue;
+                };
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngineProcess {
+            child,
+            stdin,
+            output,
+            name: None,
+            author: None,
+            options: Vec::new(),
+        };
+
+        engine.send(&UCIEngineCommand::Uci)?;
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        loop {
+            match engine.recv_until(deadline)? {
+                UCIGuiCommand::IdName(name) => engine.name = Some(name),
+                UCIGuiCommand::IdAuthor(author) => engine.author = Some(author),
+                UCIGuiCommand::Option(option) => engine.options.push(option),
+                UCIGuiCommand::UciOk => break,
+                _ => {}
+            }
+        }
+        engine.is_ready()?;
+
+        Ok(engine)
+    }
+
+    /// The name the engine sent with `id name`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The author the engine sent with `id author`.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// The options the engine sent before `uciok`.
+    pub fn options(&self) -> &[UCIOption] {
+        &self.options
+    }
+
+    pub fn send(&mut self, command: &UCIEngineCommand) -> io::Result<()> {
+        write!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    /// Sends `isready` and waits for `readyok`. Commands the engine sends in between are
+    /// thrown away.
+    pub fn is_ready(&mut self) -> io::Result<()> {
+        self.send(&UCIEngineCommand::IsReady)?;
+        self.wait_for(RESPONSE_TIMEOUT, |command| {
+            matches!(command, UCIGuiCommand::ReadyOk)
+        })
+        .map(|_| ())
+    }
+
+    /// Returns the next command of the engine without waiting for it, or `None` if there is none
+    /// yet. Fails once the engine exited and all of its commands were read.
+    pub fn try_recv(&self) -> io::Result<Option<UCIGuiCommand>> {
+        match self.output.try_recv() {
+            Ok(command) => Ok(Some(command)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(engine_exited()),
+        }
+    }
+
+    /// Waits at most `timeout` for the next command of the engine.
+    pub fn recv_timeout(&self, timeout: Duration) -> io::Result<UCIGuiCommand> {
+        self.recv_until(Instant::now() + timeout)
+    }
+
+    /// Reads the commands of the engine until one matches `predicate` and returns it.
+    pub fn wait_for(
+        &self,
+        timeout: Duration,
+        predicate: impl Fn(&UCIGuiCommand) -> bool,
+    ) -> io::Result<UCIGuiCommand> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let command = self.recv_until(deadline)?;
+            if predicate(&command) {
+                return Ok(command);
+            }
+        }
+    }
+
+    /// Stops the search and waits for the best move.
+    pub fn stop(&mut self) -> io::Result<UCIGuiCommand> {
+        self.send(&UCIEngineCommand::Stop)?;
+        self.wait_for(RESPONSE_TIMEOUT, |command| {
+            matches!(command, UCIGuiCommand::BestMove { .. })
+        })
+    }
+
+    fn recv_until(&self, deadline: Instant) -> io::Result<UCIGuiCommand> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        self.output.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => {
+                io::Error::new(io::ErrorKind::TimedOut, "engine did not answer in time")
+            }
+            RecvTimeoutError::Disconnected => engine_exited(),
+        })
+    }
+}
+
+fn engine_exited() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited")
+}
+
+impl Drop for UciEngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send(&UCIEngineCommand::Quit);
+
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
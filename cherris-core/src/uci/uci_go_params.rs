@@ -1,4 +1,4 @@
-use std::{fmt::Display, u128};
+use std::fmt::Display;
 
 use crate::uci::UCIEngineCommandParseError;
 
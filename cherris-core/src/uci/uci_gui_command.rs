@@ -1,9 +1,9 @@
-use std::fmt::Display;
+use std::{fmt::Display, num::ParseIntError, str::FromStr};
 
 use crate::{uci::UCIOption, uci::UCISearchInfo};
 
 /// Represents all UCI commands that can be sent to a GUI.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum UCIGuiCommand {
     /// This must be sent after receiving the uci command to identify the engine.
     IdName(String),
@@ -18,7 +18,10 @@ pub enum UCIGuiCommand {
     /// - The engine can send the move it likes to ponder on. The engine must not start pondering automatically.
     /// - This command must always be sent if the engine stops searching, also in pondering mode if there is a stop command, so for every go command a bestmove command is needed!
     /// - Directly before that the engine should send a final info command with the final search information, the the GUI has the complete statistics about the last search.
-    BestMove(String),
+    BestMove {
+        chess_move: String,
+        ponder: Option<String>,
+    },
     /// - This is needed for copyprotected engines. After the uciok command the engine can tell the GUI, that it will check the copy protection now. This is done by copyprotection checking.
     /// - If the check is ok the engine should send copyprotection ok, otherwise copyprotection error. If there is an error the engine should not function properly but should not quit alone. If the engine reports copyprotection error the GUI should not use this engine and display an error message instead!
     CopyProtectionChecking,
@@ -38,6 +41,53 @@ pub enum UCIGuiCommand {
     Option(UCIOption),
 }
 
+#[derive(Debug, PartialEq)]
+pub enum UCIGuiCommandParseError {
+    InvalidCommand,
+    InvalidToken,
+    /// A token that needs a value is the last one of the command.
+    MissingValue,
+    ParseInt,
+}
+
+impl From<ParseIntError> for UCIGuiCommandParseError {
+    fn from(_: ParseIntError) -> Self {
+        UCIGuiCommandParseError::ParseInt
+    }
+}
+
+impl FromStr for UCIGuiCommand {
+    type Err = UCIGuiCommandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            ["id", "name", name @ ..] => Ok(UCIGuiCommand::IdName(name.join(" "))),
+            ["id", "author", author @ ..] => Ok(UCIGuiCommand::IdAuthor(author.join(" "))),
+            ["uciok"] => Ok(UCIGuiCommand::UciOk),
+            ["readyok"] => Ok(UCIGuiCommand::ReadyOk),
+            ["bestmove", chess_move] => Ok(UCIGuiCommand::BestMove {
+                chess_move: chess_move.to_string(),
+                ponder: None,
+            }),
+            ["bestmove", chess_move, "ponder", ponder] => Ok(UCIGuiCommand::BestMove {
+                chess_move: chess_move.to_string(),
+                ponder: Some(ponder.to_string()),
+            }),
+            ["copyprotection", "checking"] => Ok(UCIGuiCommand::CopyProtectionChecking),
+            ["copyprotection", "ok"] => Ok(UCIGuiCommand::CopyProtectionOk),
+            ["copyprotection", "error"] => Ok(UCIGuiCommand::CopyProtectionError),
+            ["registration", "checking"] => Ok(UCIGuiCommand::RegistrationChecking),
+            ["registration", "ok"] => Ok(UCIGuiCommand::RegistrationOk),
+            ["registration", "error"] => Ok(UCIGuiCommand::RegistrationError),
+            ["info", ..] => Ok(UCIGuiCommand::Info(UCISearchInfo::from_str(s)?)),
+            ["option", ..] => Ok(UCIGuiCommand::Option(UCIOption::from_str(s)?)),
+            _ => Err(UCIGuiCommandParseError::InvalidCommand),
+        }
+    }
+}
+
 impl Display for UCIGuiCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -45,7 +95,14 @@ impl Display for UCIGuiCommand {
             UCIGuiCommand::IdAuthor(author) => writeln!(f, "id author {}", author),
             UCIGuiCommand::UciOk => writeln!(f, "uciok"),
             UCIGuiCommand::ReadyOk => writeln!(f, "readyok"),
-            UCIGuiCommand::BestMove(mv) => writeln!(f, "bestmove {}", mv),
+            UCIGuiCommand::BestMove {
+                chess_move,
+                ponder: None,
+            } => writeln!(f, "bestmove {}", chess_move),
+            UCIGuiCommand::BestMove {
+                chess_move,
+                ponder: Some(ponder),
+            } => writeln!(f, "bestmove {} ponder {}", chess_move, ponder),
             UCIGuiCommand::CopyProtectionChecking => writeln!(f, "copyprotection checking"),
             UCIGuiCommand::CopyProtectionOk => writeln!(f, "copyprotection ok"),
             UCIGuiCommand::CopyProtectionError => writeln!(f, "copyprotection error"),
@@ -60,7 +117,7 @@ impl Display for UCIGuiCommand {
 
 #[cfg(test)]
 mod tests {
-    use crate::uci::{UCIOptionType, UCIScore};
+    use crate::uci::{UCIOptionType, UCIScore, UCIScoreBound};
 
     use super::*;
 
@@ -93,11 +150,27 @@ mod tests {
     #[test]
     fn display_bestmove() {
         assert_eq!(
-            UCIGuiCommand::BestMove("e2e4".to_string()).to_string(),
+            UCIGuiCommand::BestMove {
+                chess_move: "e2e4".to_string(),
+                ponder: None,
+            }
+            .to_string(),
             "bestmove e2e4\n"
         );
     }
 
+    #[test]
+    fn display_bestmove_ponder() {
+        assert_eq!(
+            UCIGuiCommand::BestMove {
+                chess_move: "e2e4".to_string(),
+                ponder: Some("e7e5".to_string()),
+            }
+            .to_string(),
+            "bestmove e2e4 ponder e7e5\n"
+        );
+    }
+
     #[test]
     fn display_copyprotection_checking() {
         let command = UCIGuiCommand::CopyProtectionChecking.to_string();
@@ -137,13 +210,14 @@ mod tests {
     #[test]
     fn display_info() {
         let command = UCIGuiCommand::Info(UCISearchInfo {
-            depth: 1,
-            seldepth: 2,
-            score: UCIScore::Centipawns(3),
-            time: 4,
-            nodes: 5,
+            depth: Some(1),
+            seldepth: Some(2),
+            score: Some(UCIScore::Centipawns(3)),
+            time: Some(4),
+            nodes: Some(5),
             pv: vec!["e2e4".to_string()],
-            nps: 6,
+            nps: Some(6),
+            ..Default::default()
         });
         assert_eq!(
             command.to_string(),
@@ -151,6 +225,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_info_multipv() {
+        let command = UCIGuiCommand::Info(UCISearchInfo {
+            depth: Some(1),
+            seldepth: Some(2),
+            multipv: Some(2),
+            score: Some(UCIScore::Mate(-3)),
+            time: Some(4),
+            nodes: Some(5),
+            pv: vec!["e2e4".to_string(), "e7e5".to_string()],
+            nps: Some(6),
+            ..Default::default()
+        });
+        assert_eq!(
+            command.to_string(),
+            "info depth 1 seldepth 2 multipv 2 score mate -3 time 4 nodes 5 nps 6 pv e2e4 e7e5\n"
+        );
+    }
+
+    #[test]
+    fn display_info_bounds() {
+        let mut info = UCISearchInfo {
+            depth: Some(8),
+            seldepth: Some(12),
+            multipv: Some(1),
+            score: Some(UCIScore::Centipawns(45)),
+            bound: UCIScoreBound::LowerBound,
+            time: Some(4),
+            nodes: Some(5),
+            pv: vec!["e2e4".to_string()],
+            nps: Some(6),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            UCIGuiCommand::Info(info.clone()).to_string(),
+            "info depth 8 seldepth 12 multipv 1 score cp 45 lowerbound time 4 nodes 5 nps 6 pv e2e4\n"
+        );
+
+        info.bound = UCIScoreBound::UpperBound;
+
+        assert_eq!(
+            UCIGuiCommand::Info(info).to_string(),
+            "info depth 8 seldepth 12 multipv 1 score cp 45 upperbound time 4 nodes 5 nps 6 pv e2e4\n"
+        );
+    }
+
     #[test]
     fn display_option() {
         let command = UCIGuiCommand::Option(UCIOption {
@@ -167,4 +288,86 @@ mod tests {
             "option name Selectivity type spin default 2 min 0 max 4\n"
         );
     }
+
+    #[test]
+    fn parse_id() {
+        assert_eq!(
+            UCIGuiCommand::from_str("id name Cherris 0.1"),
+            Ok(UCIGuiCommand::IdName("Cherris 0.1".to_string()))
+        );
+        assert_eq!(
+            UCIGuiCommand::from_str("id author Johannes Thiel"),
+            Ok(UCIGuiCommand::IdAuthor("Johannes Thiel".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_bestmove() {
+        assert_eq!(
+            UCIGuiCommand::from_str("bestmove e2e4"),
+            Ok(UCIGuiCommand::BestMove {
+                chess_move: "e2e4".to_string(),
+                ponder: None,
+            })
+        );
+        assert_eq!(
+            UCIGuiCommand::from_str("bestmove e2e4 ponder e7e5\n"),
+            Ok(UCIGuiCommand::BestMove {
+                chess_move: "e2e4".to_string(),
+                ponder: Some("e7e5".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_info() {
+        assert_eq!(
+            UCIGuiCommand::from_str("info depth 3 score cp 10 pv e2e4"),
+            Ok(UCIGuiCommand::Info(UCISearchInfo {
+                depth: Some(3),
+                score: Some(UCIScore::Centipawns(10)),
+                pv: vec!["e2e4".to_string()],
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            UCIGuiCommand::from_str(""),
+            Err(UCIGuiCommandParseError::InvalidCommand)
+        );
+        assert_eq!(
+            UCIGuiCommand::from_str("bestmove"),
+            Err(UCIGuiCommandParseError::InvalidCommand)
+        );
+        assert_eq!(
+            UCIGuiCommand::from_str("info depth x"),
+            Err(UCIGuiCommandParseError::ParseInt)
+        );
+    }
+
+    #[test]
+    fn display_parse_roundtrip() {
+        let commands = [
+            UCIGuiCommand::IdName("Cherris".to_string()),
+            UCIGuiCommand::UciOk,
+            UCIGuiCommand::ReadyOk,
+            UCIGuiCommand::CopyProtectionChecking,
+            UCIGuiCommand::RegistrationError,
+            UCIGuiCommand::Option(UCIOption {
+                id: "Selectivity".to_string(),
+                option_type: UCIOptionType::Spin,
+                default: Some("2".to_string()),
+                min: Some("0".to_string()),
+                max: Some("4".to_string()),
+                var: vec![],
+            }),
+        ];
+
+        for command in commands {
+            assert_eq!(UCIGuiCommand::from_str(&command.to_string()), Ok(command));
+        }
+    }
 }
@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use crate::{
+    Color, Game, LAN, Move, Position,
+    uci::{UCIEngineCommand, UCIPositionParams},
+};
+
+impl Move {
+    /// Converts the move to the notation used by UCI, which writes castling as a king move.
+    pub fn to_uci(self, color: Color) -> String {
+        match (self, color) {
+            (Move::CastleShort, Color::White) => "e1g1".to_string(),
+            (Move::CastleShort, Color::Black) => "e8g8".to_string(),
+            (Move::CastleLong, Color::White) => "e1c1".to_string(),
+            (Move::CastleLong, Color::Black) => "e8c8".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Converts a move sent by an engine into a move in `position`.
+    pub fn from_uci(uci_move: &str, position: &Position) -> Option<Move> {
+        if uci_move.len() < 4 {
+            return None;
+        }
+
+        let lan = LAN::from_str(uci_move).ok()?;
+        Move::from_lan(&lan, position).ok()
+    }
+}
+
+impl UCIEngineCommand {
+    /// Returns the `position` command for the current position of `game`.
+    pub fn position_of(game: &Game) -> UCIEngineCommand {
+        let mut position = *game.starting_position();
+        let moves = game
+            .move_history()
+            .iter()
+            .map(|mv| {
+                let uci_move = mv.to_uci(position.color_to_move);
+                position.make_move(*mv);
+                uci_move
+            })
+            .collect();
+
+        UCIEngineCommand::Position(UCIPositionParams {
+            fen: game.starting_position().to_string(),
+            moves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate_lookup_tables;
+
+    use super::*;
+
+    #[test]
+    fn castling_to_uci() {
+        assert_eq!(Move::CastleShort.to_uci(Color::White), "e1g1");
+        assert_eq!(Move::CastleLong.to_uci(Color::Black), "e8c8");
+    }
+
+    #[test]
+    fn move_from_uci() {
+        generate_lookup_tables();
+        let position =
+            Position::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").expect("Valid fen");
+
+        assert!(Move::from_uci("e1c1", &position).is_some_and(|mv| mv == Move::CastleLong));
+        assert!(Move::from_uci("a1a8", &position).is_some());
+        assert!(Move::from_uci("e1e3", &position).is_none());
+        assert!(Move::from_uci("e1", &position).is_none());
+        assert!(Move::from_uci("castle", &position).is_none());
+    }
+
+    #[test]
+    fn position_of_game() {
+        generate_lookup_tables();
+        let mut game = Game::new();
+        for uci_move in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"] {
+            let mv = Move::from_uci(uci_move, game.position()).expect("Legal move");
+            game.make_move(mv);
+        }
+
+        assert_eq!(
+            UCIEngineCommand::position_of(&game),
+            UCIEngineCommand::Position(UCIPositionParams {
+                fen: Position::default().to_string(),
+                moves: ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"]
+                    .map(str::to_string)
+                    .to_vec(),
+            })
+        );
+    }
+}
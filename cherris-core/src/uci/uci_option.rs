@@ -1,7 +1,9 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
+
+use crate::uci::UCIGuiCommandParseError;
 
 /// An option that can be set on a UCI server.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UCIOption {
     pub id: String,
     pub option_type: UCIOptionType,
@@ -12,7 +14,7 @@ pub struct UCIOption {
 }
 
 /// The type of option. There are 5 different types of options the engine can send.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum UCIOptionType {
     /// A checkbox that can either be true or false
     Check,
@@ -38,6 +40,94 @@ impl Display for UCIOptionType {
     }
 }
 
+impl FromStr for UCIOptionType {
+    type Err = UCIGuiCommandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "check" => Ok(UCIOptionType::Check),
+            "spin" => Ok(UCIOptionType::Spin),
+            "combo" => Ok(UCIOptionType::Combo),
+            "button" => Ok(UCIOptionType::Button),
+            "string" => Ok(UCIOptionType::String),
+            _ => Err(UCIGuiCommandParseError::InvalidToken),
+        }
+    }
+}
+
+impl FromStr for UCIOption {
+    type Err = UCIGuiCommandParseError;
+
+    /// Parses an option, with or without the leading `option`. The name and the values can
+    /// contain spaces.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[derive(PartialEq)]
+        enum Token {
+            Empty,
+            Name,
+            Type,
+            Default,
+            Min,
+            Max,
+            Var,
+        }
+
+        let mut active_token = Token::Empty;
+        let mut name = Vec::new();
+        let mut option_type = Vec::new();
+        let mut default = None;
+        let mut min = None;
+        let mut max = None;
+        let mut var: Vec<Vec<&str>> = Vec::new();
+
+        for token in s.split_whitespace() {
+            match token {
+                "option" if active_token == Token::Empty => continue,
+                "name" => active_token = Token::Name,
+                "type" => active_token = Token::Type,
+                "default" => {
+                    active_token = Token::Default;
+                    default = Some(Vec::new());
+                }
+                "min" => {
+                    active_token = Token::Min;
+                    min = Some(Vec::new());
+                }
+                "max" => {
+                    active_token = Token::Max;
+                    max = Some(Vec::new());
+                }
+                "var" => {
+                    active_token = Token::Var;
+                    var.push(Vec::new());
+                }
+                _ => match active_token {
+                    Token::Name => name.push(token),
+                    Token::Type => option_type.push(token),
+                    Token::Default => default.get_or_insert_default().push(token),
+                    Token::Min => min.get_or_insert_default().push(token),
+                    Token::Max => max.get_or_insert_default().push(token),
+                    Token::Var => var.last_mut().expect("var was pushed").push(token),
+                    Token::Empty => return Err(UCIGuiCommandParseError::InvalidToken),
+                },
+            }
+        }
+
+        if name.is_empty() {
+            return Err(UCIGuiCommandParseError::MissingValue);
+        }
+
+        Ok(UCIOption {
+            id: name.join(" "),
+            option_type: option_type.join(" ").parse()?,
+            default: default.map(|default| default.join(" ")),
+            min: min.map(|min| min.join(" ")),
+            max: max.map(|max| max.join(" ")),
+            var: var.into_iter().map(|var| var.join(" ")).collect(),
+        })
+    }
+}
+
 impl Display for UCIOption {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "name {} type {}", self.id, self.option_type)?;
@@ -73,4 +163,70 @@ mod tests {
         assert_eq!(UCIOptionType::Button.to_string(), "button");
         assert_eq!(UCIOptionType::String.to_string(), "string");
     }
+
+    #[test]
+    fn parse_spin() {
+        assert_eq!(
+            UCIOption::from_str("option name Hash type spin default 16 min 1 max 33554432"),
+            Ok(UCIOption {
+                id: "Hash".to_string(),
+                option_type: UCIOptionType::Spin,
+                default: Some("16".to_string()),
+                min: Some("1".to_string()),
+                max: Some("33554432".to_string()),
+                var: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_combo() {
+        assert_eq!(
+            UCIOption::from_str(
+                "option name Play Style type combo default Normal var Very Solid var Normal"
+            ),
+            Ok(UCIOption {
+                id: "Play Style".to_string(),
+                option_type: UCIOptionType::Combo,
+                default: Some("Normal".to_string()),
+                min: None,
+                max: None,
+                var: vec!["Very Solid".to_string(), "Normal".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_empty_string_default() {
+        let option = UCIOption::from_str("option name NalimovPath type string default").unwrap();
+
+        assert_eq!(option.option_type, UCIOptionType::String);
+        assert_eq!(option.default.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            UCIOption::from_str("option type check"),
+            Err(UCIGuiCommandParseError::MissingValue)
+        );
+        assert_eq!(
+            UCIOption::from_str("option name Ponder type checkbox"),
+            Err(UCIGuiCommandParseError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn display_parse_roundtrip() {
+        let option = UCIOption {
+            id: "Clear Hash".to_string(),
+            option_type: UCIOptionType::Button,
+            default: None,
+            min: None,
+            max: None,
+            var: vec![],
+        };
+
+        assert_eq!(UCIOption::from_str(&option.to_string()), Ok(option));
+    }
 }
@@ -1,35 +1,202 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Clone)]
+use crate::uci::UCIGuiCommandParseError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UCIScore {
     Centipawns(i16),
     Mate(i16),
 }
 
-#[derive(Clone)]
+/// Tells the GUI whether a score is exact or only a bound, which is the case when the search failed
+/// outside of its aspiration window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UCIScoreBound {
+    #[default]
+    Exact,
+    /// The real score is at least the reported one.
+    LowerBound,
+    /// The real score is at most the reported one.
+    UpperBound,
+}
+
+/// The information an engine sends about its search. Engines only send the fields that changed,
+/// so every field is optional.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct UCISearchInfo {
-    pub depth: u8,
-    pub seldepth: u8,
-    pub time: u128,
-    pub nodes: u64,
-    pub score: UCIScore,
+    pub depth: Option<u8>,
+    pub seldepth: Option<u8>,
+    /// The index of this line when the engine reports more than one line, starting at 1.
+    pub multipv: Option<u16>,
+    pub time: Option<u128>,
+    pub nodes: Option<u64>,
+    pub score: Option<UCIScore>,
+    pub bound: UCIScoreBound,
     pub pv: Vec<String>,
-    pub nps: u64,
+    /// The move that is currently searched at the root.
+    pub currmove: Option<String>,
+    /// The index of `currmove` among the root moves, starting at 1.
+    pub currmovenumber: Option<u32>,
+    /// How full the hash table is in permill.
+    pub hashfull: Option<u16>,
+    pub nps: Option<u64>,
+    /// The number of positions found in the endgame tablebases.
+    pub tbhits: Option<u64>,
+    /// The number of positions found in the shredder endgame databases.
+    pub sbhits: Option<u64>,
+    /// The cpu usage of the engine in permill.
+    pub cpuload: Option<u16>,
+    /// A move followed by the line that refutes it.
+    pub refutation: Vec<String>,
+    /// The line the engine is currently searching.
+    pub currline: Vec<String>,
+    /// A message that the GUI should show to the user.
+    pub string: Option<String>,
 }
 
 impl Display for UCISearchInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "depth {} seldepth {} score {} time {} nodes {} nps {} pv",
-            self.depth, self.seldepth, self.score, self.time, self.nodes, self.nps
-        )?;
-
-        for mv in &self.pv {
-            write!(f, " {}", mv)?;
+        let mut fields = Vec::new();
+
+        let mut field = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push(format!("{} {}", name, value));
+            }
+        };
+        field("depth", self.depth.map(|depth| depth.to_string()));
+        field(
+            "seldepth",
+            self.seldepth.map(|seldepth| seldepth.to_string()),
+        );
+        field("multipv", self.multipv.map(|multipv| multipv.to_string()));
+        field(
+            "score",
+            self.score.map(|score| format!("{}{}", score, self.bound)),
+        );
+        field("currmove", self.currmove.clone());
+        field(
+            "currmovenumber",
+            self.currmovenumber.map(|number| number.to_string()),
+        );
+        field(
+            "hashfull",
+            self.hashfull.map(|hashfull| hashfull.to_string()),
+        );
+        field("time", self.time.map(|time| time.to_string()));
+        field("nodes", self.nodes.map(|nodes| nodes.to_string()));
+        field("nps", self.nps.map(|nps| nps.to_string()));
+        field("tbhits", self.tbhits.map(|tbhits| tbhits.to_string()));
+        field("sbhits", self.sbhits.map(|sbhits| sbhits.to_string()));
+        field("cpuload", self.cpuload.map(|cpuload| cpuload.to_string()));
+
+        for (name, line) in [
+            ("refutation", &self.refutation),
+            ("currline", &self.currline),
+            ("pv", &self.pv),
+        ] {
+            if !line.is_empty() {
+                fields.push(format!("{} {}", name, line.join(" ")));
+            }
+        }
+
+        // The string has to come last, as it takes up the rest of the line.
+        if let Some(string) = &self.string {
+            fields.push(format!("string {}", string));
         }
 
-        Ok(())
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+impl FromStr for UCISearchInfo {
+    type Err = UCIGuiCommandParseError;
+
+    /// Parses the fields of an info, with or without the leading `info`, in any order.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const KEYWORDS: [&str; 17] = [
+            "depth",
+            "seldepth",
+            "time",
+            "nodes",
+            "pv",
+            "multipv",
+            "score",
+            "currmove",
+            "currmovenumber",
+            "hashfull",
+            "nps",
+            "tbhits",
+            "sbhits",
+            "cpuload",
+            "string",
+            "refutation",
+            "currline",
+        ];
+
+        let mut info = UCISearchInfo::default();
+        let mut tokens = s.split_whitespace().peekable();
+        if tokens.peek() == Some(&"info") {
+            tokens.next();
+        }
+
+        while let Some(keyword) = tokens.next() {
+            let mut value = || tokens.next().ok_or(UCIGuiCommandParseError::MissingValue);
+
+            match keyword {
+                "depth" => info.depth = Some(value()?.parse()?),
+                "seldepth" => info.seldepth = Some(value()?.parse()?),
+                "time" => info.time = Some(value()?.parse()?),
+                "nodes" => info.nodes = Some(value()?.parse()?),
+                "multipv" => info.multipv = Some(value()?.parse()?),
+                "score" => {
+                    info.score = Some(match value()? {
+                        "cp" => UCIScore::Centipawns(value()?.parse()?),
+                        "mate" => UCIScore::Mate(value()?.parse()?),
+                        _ => return Err(UCIGuiCommandParseError::InvalidToken),
+                    });
+                    info.bound = match tokens.peek() {
+                        Some(&"lowerbound") => UCIScoreBound::LowerBound,
+                        Some(&"upperbound") => UCIScoreBound::UpperBound,
+                        _ => UCIScoreBound::Exact,
+                    };
+                    if info.bound != UCIScoreBound::Exact {
+                        tokens.next();
+                    }
+                }
+                "currmove" => info.currmove = Some(value()?.to_string()),
+                "currmovenumber" => info.currmovenumber = Some(value()?.parse()?),
+                "hashfull" => info.hashfull = Some(value()?.parse()?),
+                "nps" => info.nps = Some(value()?.parse()?),
+                "tbhits" => info.tbhits = Some(value()?.parse()?),
+                "sbhits" => info.sbhits = Some(value()?.parse()?),
+                "cpuload" => info.cpuload = Some(value()?.parse()?),
+                "string" => {
+                    info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" "));
+                }
+                "pv" | "refutation" | "currline" => {
+                    let mut line = Vec::new();
+                    while let Some(mv) = tokens.next_if(|token| !KEYWORDS.contains(token)) {
+                        line.push(mv.to_string());
+                    }
+
+                    match keyword {
+                        "pv" => info.pv = line,
+                        "refutation" => info.refutation = line,
+                        // The line can start with the number of the cpu that searches it.
+                        _ => {
+                            if line.first().is_some_and(|cpu| cpu.parse::<u32>().is_ok()) {
+                                line.remove(0);
+                            }
+                            info.currline = line;
+                        }
+                    }
+                }
+                // Unknown tokens, like the `wdl` some engines send, are skipped.
+                _ => {}
+            }
+        }
+
+        Ok(info)
     }
 }
 
@@ -41,3 +208,136 @@ impl Display for UCIScore {
         }
     }
 }
+
+impl Display for UCIScoreBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UCIScoreBound::Exact => Ok(()),
+            UCIScoreBound::LowerBound => write!(f, " lowerbound"),
+            UCIScoreBound::UpperBound => write!(f, " upperbound"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_info() {
+        let info = UCISearchInfo::from_str(
+            "info depth 12 seldepth 20 multipv 1 score cp 35 time 150 nodes 20000 nps 133333 pv e2e4 e7e5 g1f3",
+        )
+        .unwrap();
+
+        assert_eq!(
+            info,
+            UCISearchInfo {
+                depth: Some(12),
+                seldepth: Some(20),
+                multipv: Some(1),
+                score: Some(UCIScore::Centipawns(35)),
+                time: Some(150),
+                nodes: Some(20000),
+                nps: Some(133333),
+                pv: vec!["e2e4".to_string(), "e7e5".to_string(), "g1f3".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_info_any_order() {
+        let info = UCISearchInfo::from_str("pv d2d4 d7d5 score mate -3 upperbound nodes 7 depth 5")
+            .unwrap();
+
+        assert_eq!(info.depth, Some(5));
+        assert_eq!(info.nodes, Some(7));
+        assert_eq!(info.score, Some(UCIScore::Mate(-3)));
+        assert_eq!(info.bound, UCIScoreBound::UpperBound);
+        assert_eq!(info.pv, vec!["d2d4", "d7d5"]);
+    }
+
+    #[test]
+    fn parse_info_current_move() {
+        let info = UCISearchInfo::from_str(
+            "info currmove e2e4 currmovenumber 1 hashfull 250 tbhits 3 cpuload 900 currline 1 e2e4 e7e5",
+        )
+        .unwrap();
+
+        assert_eq!(info.currmove.as_deref(), Some("e2e4"));
+        assert_eq!(info.currmovenumber, Some(1));
+        assert_eq!(info.hashfull, Some(250));
+        assert_eq!(info.tbhits, Some(3));
+        assert_eq!(info.cpuload, Some(900));
+        assert_eq!(info.currline, vec!["e2e4", "e7e5"]);
+    }
+
+    #[test]
+    fn parse_info_string() {
+        let info = UCISearchInfo::from_str("info depth 3 string NNUE depth 3 loaded").unwrap();
+
+        assert_eq!(info.depth, Some(3));
+        assert_eq!(info.string.as_deref(), Some("NNUE depth 3 loaded"));
+    }
+
+    #[test]
+    fn parse_info_lowerbound() {
+        let info =
+            UCISearchInfo::from_str("info score cp -20 lowerbound refutation d1h5 g6h5").unwrap();
+
+        assert_eq!(info.score, Some(UCIScore::Centipawns(-20)));
+        assert_eq!(info.bound, UCIScoreBound::LowerBound);
+        assert_eq!(info.refutation, vec!["d1h5", "g6h5"]);
+    }
+
+    #[test]
+    fn parse_info_invalid() {
+        assert_eq!(
+            UCISearchInfo::from_str("info depth"),
+            Err(UCIGuiCommandParseError::MissingValue)
+        );
+        assert_eq!(
+            UCISearchInfo::from_str("info depth deep"),
+            Err(UCIGuiCommandParseError::ParseInt)
+        );
+        assert_eq!(
+            UCISearchInfo::from_str("info score pawns 3"),
+            Err(UCIGuiCommandParseError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn parse_info_unknown_tokens() {
+        assert_eq!(
+            UCISearchInfo::from_str("info speed 3"),
+            Ok(UCISearchInfo::default())
+        );
+
+        let info =
+            UCISearchInfo::from_str("info depth 20 score cp 31 wdl 400 500 100 pv e2e4 c7c5")
+                .unwrap();
+
+        assert_eq!(info.depth, Some(20));
+        assert_eq!(info.score, Some(UCIScore::Centipawns(31)));
+        assert_eq!(info.bound, UCIScoreBound::Exact);
+        assert_eq!(info.pv, vec!["e2e4", "c7c5"]);
+    }
+
+    #[test]
+    fn display_parse_roundtrip() {
+        let info = UCISearchInfo {
+            depth: Some(8),
+            currmove: Some("g1f3".to_string()),
+            currmovenumber: Some(4),
+            hashfull: Some(10),
+            score: Some(UCIScore::Centipawns(12)),
+            bound: UCIScoreBound::LowerBound,
+            pv: vec!["g1f3".to_string()],
+            string: Some("hello world".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(UCISearchInfo::from_str(&info.to_string()), Ok(info));
+    }
+}
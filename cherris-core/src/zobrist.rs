@@ -1,4 +1,4 @@
-use crate::{CastlingRights, Color, Move, Position, Role, Square};
+use crate::{Board, CastlingRights, Color, Error, Move, Piece, Position, Role, Square};
 
 const ZOBRIST_SIZE: usize = Role::COUNT * Color::COUNT * Square::COUNT;
 static ZOBRIST_PIECES: [u64; ZOBRIST_SIZE] = generate_zobrist_pieces();
@@ -12,43 +12,110 @@ pub struct Zobrist(u64);
 
 impl Zobrist {
     /// The Zobrist key for the starting `Position`.
-    pub const DEFAULT: Zobrist = Zobrist(3821593027773340683);
+    pub const DEFAULT: Zobrist = Zobrist(7152505143963832509);
 
     pub fn key(&self) -> u64 {
         self.0
     }
 
-    fn hash(position: &Position) -> Zobrist {
+    /// Computes the Zobrist key of a position from scratch.
+    pub fn from_position(position: &Position) -> Zobrist {
         let mut hash = 0;
         for square in position.board.occupied {
             let piece = position.board.piece_on(square).unwrap();
-            let key =
-                ZOBRIST_PIECES[piece.color.to_index() * piece.role.to_index() + square.to_index()];
-
-            hash ^= key;
+            hash ^= piece_key(piece.color, piece.role, square);
         }
 
         if position.color_to_move == Color::Black {
             hash ^= ZOBRIST_BLACK;
         }
 
-        hash ^= ZOBRIST_CASTLING
-            [position.castling_rights[0].to_index() + position.castling_rights[1].to_index()];
+        hash ^= castling_key(position.castling_rights);
 
         if let Some(en_passant_sqaure) = position.en_passant_square {
-            let file = en_passant_sqaure.to_index() % 8;
-            hash ^= ZOBRIST_EN_PASSANT[file];
+            hash ^= en_passant_key(en_passant_sqaure);
+        }
+
+        Zobrist(hash)
+    }
+
+    /// Computes the key of only the pawns of a position, which is the same for all positions with
+    /// the same pawn structure.
+    pub fn pawns_from_position(position: &Position) -> Zobrist {
+        let mut hash = 0;
+        for color in Color::ALL {
+            for square in position.board.role[Role::Pawn] & position.board.color[color] {
+                hash ^= piece_key(color, Role::Pawn, square);
+            }
+        }
+
+        Zobrist(hash)
+    }
+
+    /// Computes the key of the material of a position, which only depends on the number of pieces
+    /// of each type and not on their placement.
+    pub fn material_from_position(position: &Position) -> Zobrist {
+        let mut counts = [[0; Role::COUNT]; Color::COUNT];
+        for color in Color::ALL {
+            for role in Role::ALL {
+                counts[color][role] =
+                    (position.board.role[role] & position.board.color[color]).population_count();
+            }
+        }
+
+        Zobrist::from_material(&counts)
+    }
+
+    /// Computes the material key for the given number of pieces of each color and role.
+    pub fn from_material(counts: &[[u32; Role::COUNT]; Color::COUNT]) -> Zobrist {
+        let mut hash = 0;
+        for color in Color::ALL {
+            for role in Role::ALL {
+                for count in 0..counts[color][role] {
+                    hash ^= material_key(color, role, count);
+                }
+            }
         }
 
         Zobrist(hash)
     }
 
+    /// Computes the material key of a signature like `KRPvKR`, which lists the pieces of white
+    /// before the `v` and those of black after it.
+    pub fn from_material_signature(signature: &str) -> Result<Zobrist, Error> {
+        let (white, black) = signature.split_once('v').ok_or(Error::ParsePiece)?;
+
+        let mut counts = [[0; Role::COUNT]; Color::COUNT];
+        for (color, pieces) in [(Color::White, white), (Color::Black, black)] {
+            for char in pieces.chars() {
+                let piece = Piece::try_from(char.to_ascii_uppercase())?;
+                counts[color][piece.role] += 1;
+            }
+        }
+
+        Ok(Zobrist::from_material(&counts))
+    }
+
     pub fn update_castling_right(&mut self, castling_rights: [CastlingRights; Color::COUNT]) {
-        self.0 ^= ZOBRIST_CASTLING[castling_rights[0].to_index() + castling_rights[1].to_index()];
+        self.0 ^= castling_key(castling_rights);
     }
 
-    pub fn update(&mut self, chess_move: Move, color: Color) {
+    /// Switches the color to move, which is all a null move changes besides the en passant square.
+    pub fn update_color_to_move(&mut self) {
         self.0 ^= ZOBRIST_BLACK;
+    }
+
+    /// Adds or removes the en passant square of a position.
+    pub fn update_en_passant(&mut self, en_passant_square: Option<Square>) {
+        if let Some(en_passant_square) = en_passant_square {
+            self.0 ^= en_passant_key(en_passant_square);
+        }
+    }
+
+    /// Updates the pieces, the color to move and the new en passant square for a move of `color`.
+    /// The castling rights and the previous en passant square are updated separately.
+    pub fn update(&mut self, chess_move: Move, color: Color) {
+        self.update_color_to_move();
 
         match chess_move {
             Move::Standard {
@@ -59,88 +126,136 @@ impl Zobrist {
                 promotion,
                 en_passant_square,
             } => {
-                self.0 ^= ZOBRIST_PIECES[color.to_index() * role.to_index() + from.to_index()];
-                if let Some(role) = promotion {
-                    self.0 ^= ZOBRIST_PIECES[color.to_index() * role.to_index() + to.to_index()];
-                } else {
-                    self.0 ^= ZOBRIST_PIECES[color.to_index() * role.to_index() + to.to_index()];
-                }
+                self.0 ^= piece_key(color, role, from);
+                self.0 ^= piece_key(color, promotion.unwrap_or(role), to);
 
                 if let Some(role) = capture {
-                    self.0 ^= ZOBRIST_PIECES[(!color).to_index() * role.to_index() + to.to_index()];
+                    self.0 ^= piece_key(!color, role, to);
                 }
 
-                if let Some(en_passant_sqaure) = en_passant_square {
-                    let file = en_passant_sqaure.to_index() % 8;
-                    self.0 ^= ZOBRIST_EN_PASSANT[file];
-                }
+                self.update_en_passant(en_passant_square);
             }
             Move::EnPassant { from, to, target } => {
-                self.0 ^=
-                    ZOBRIST_PIECES[color.to_index() * Role::Pawn.to_index() + from.to_index()];
-                self.0 ^= ZOBRIST_PIECES[color.to_index() * Role::Pawn.to_index() + to.to_index()];
-
-                self.0 ^=
-                    ZOBRIST_PIECES[!(color).to_index() * Role::Pawn.to_index() + target.to_index()];
+                self.0 ^= piece_key(color, Role::Pawn, from);
+                self.0 ^= piece_key(color, Role::Pawn, to);
+                self.0 ^= piece_key(!color, Role::Pawn, target);
             }
-            Move::CastleShort => match color {
-                Color::White => {
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::E1.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::G1.to_index()];
-
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::H1.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::F1.to_index()];
-                }
-                Color::Black => {
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::E8.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::G8.to_index()];
-
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::H8.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::F8.to_index()];
-                }
-            },
-            Move::CastleLong => match color {
-                Color::White => {
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::E1.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::C1.to_index()];
-
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::A1.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::D1.to_index()];
+            Move::CastleShort => {
+                let (king_from, king_to, rook_from, rook_to) = match color {
+                    Color::White => (Square::E1, Square::G1, Square::H1, Square::F1),
+                    Color::Black => (Square::E8, Square::G8, Square::H8, Square::F8),
+                };
+                self.update_castling_move(color, king_from, king_to, rook_from, rook_to);
+            }
+            Move::CastleLong => {
+                let (king_from, king_to, rook_from, rook_to) = match color {
+                    Color::White => (Square::E1, Square::C1, Square::A1, Square::D1),
+                    Color::Black => (Square::E8, Square::C8, Square::A8, Square::D8),
+                };
+                self.update_castling_move(color, king_from, king_to, rook_from, rook_to);
+            }
+        }
+    }
+
+    /// Updates the pawn key for a move of `color`.
+    pub fn update_pawns(&mut self, chess_move: Move, color: Color) {
+        match chess_move {
+            Move::Standard {
+                from,
+                to,
+                role,
+                capture,
+                promotion,
+                ..
+            } => {
+                if role == Role::Pawn {
+                    self.0 ^= piece_key(color, Role::Pawn, from);
+                    if promotion.is_none() {
+                        self.0 ^= piece_key(color, Role::Pawn, to);
+                    }
                 }
-                Color::Black => {
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::E8.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::King.to_index() + Square::C8.to_index()];
-
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::A8.to_index()];
-                    self.0 ^= ZOBRIST_PIECES
-                        [color.to_index() * Role::Rook.to_index() + Square::D8.to_index()];
+
+                if capture == Some(Role::Pawn) {
+                    self.0 ^= piece_key(!color, Role::Pawn, to);
                 }
-            },
+            }
+            Move::EnPassant { from, to, target } => {
+                self.0 ^= piece_key(color, Role::Pawn, from);
+                self.0 ^= piece_key(color, Role::Pawn, to);
+                self.0 ^= piece_key(!color, Role::Pawn, target);
+            }
+            Move::CastleShort | Move::CastleLong => {}
         }
     }
+
+    /// Updates the material key for a move of `color`. Has to be called with the board before the
+    /// move is made.
+    pub fn update_material(&mut self, chess_move: Move, color: Color, board: &Board) {
+        let count =
+            |color: Color, role: Role| (board.role[role] & board.color[color]).population_count();
+
+        let (capture, promotion) = match chess_move {
+            Move::Standard {
+                capture, promotion, ..
+            } => (capture, promotion),
+            Move::EnPassant { .. } => (Some(Role::Pawn), None),
+            Move::CastleShort | Move::CastleLong => (None, None),
+        };
+
+        if let Some(role) = capture {
+            self.0 ^= material_key(!color, role, count(!color, role) - 1);
+        }
+
+        if let Some(role) = promotion {
+            self.0 ^= material_key(color, Role::Pawn, count(color, Role::Pawn) - 1);
+            self.0 ^= material_key(color, role, count(color, role));
+        }
+    }
+
+    fn update_castling_move(
+        &mut self,
+        color: Color,
+        king_from: Square,
+        king_to: Square,
+        rook_from: Square,
+        rook_to: Square,
+    ) {
+        self.0 ^= piece_key(color, Role::King, king_from);
+        self.0 ^= piece_key(color, Role::King, king_to);
+        self.0 ^= piece_key(color, Role::Rook, rook_from);
+        self.0 ^= piece_key(color, Role::Rook, rook_to);
+    }
 }
 
 impl From<&Position> for Zobrist {
     fn from(value: &Position) -> Self {
-        Zobrist::hash(value)
+        Zobrist::from_position(value)
     }
 }
 
+#[inline]
+fn piece_key(color: Color, role: Role, square: Square) -> u64 {
+    ZOBRIST_PIECES
+        [(color.to_index() * Role::COUNT + role.to_index()) * Square::COUNT + square.to_index()]
+}
+
+/// The material key uses the piece keys with the number of a piece instead of its square.
+#[inline]
+fn material_key(color: Color, role: Role, count: u32) -> u64 {
+    piece_key(color, role, Square::from_index(count as u8))
+}
+
+#[inline]
+fn castling_key(castling_rights: [CastlingRights; Color::COUNT]) -> u64 {
+    ZOBRIST_CASTLING
+        [castling_rights[Color::White].to_index() + castling_rights[Color::Black].to_index() * 4]
+}
+
+#[inline]
+fn en_passant_key(en_passant_square: Square) -> u64 {
+    ZOBRIST_EN_PASSANT[en_passant_square.to_index() % 8]
+}
+
 const fn generate_zobrist_pieces() -> [u64; ZOBRIST_SIZE] {
     let mut zobrist_keys = [0; ZOBRIST_SIZE];
     let mut index = 0;
@@ -161,7 +276,7 @@ const fn generate_zobrist_castling() -> [u64; 16] {
     let mut index = 0;
     let mut seed = 832053;
 
-    while index < 4 {
+    while index < 16 {
         let (key, next_seed) = Prng::rand(seed);
         zobrist_keys[index] = key;
         seed = next_seed;
@@ -201,7 +316,9 @@ impl Prng {
 
 #[cfg(test)]
 mod tests {
-    use crate::CastlingRights;
+    use std::str::FromStr;
+
+    use crate::{CastlingRights, generate_lookup_tables};
 
     use super::*;
 
@@ -209,7 +326,131 @@ mod tests {
     fn zobrist_start_pos() {
         let position = Position::default();
 
-        assert_eq!(position.zobrist.0, 3821593027773340683);
+        assert_eq!(position.zobrist, Zobrist::DEFAULT);
+        assert_eq!(Zobrist::from_position(&position), Zobrist::DEFAULT);
+    }
+
+    #[test]
+    fn zobrist_from_fen() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mirrored = Position::from_str("4k2r/8/8/8/8/8/8/4K3 w k - 0 1").unwrap();
+
+        assert_ne!(position.zobrist, Zobrist::DEFAULT);
+        assert_eq!(position.zobrist, Zobrist::from_position(&position));
+        assert_ne!(position.zobrist, mirrored.zobrist);
+    }
+
+    #[test]
+    fn zobrist_diff_pieces_same_square() {
+        let position = Position::from_str("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let rook = Position::from_str("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").unwrap();
+
+        assert_ne!(position.zobrist, rook.zobrist);
+    }
+
+    #[test]
+    fn pawn_key_ignores_pieces() {
+        let position = Position::from_str("4k3/3p4/8/8/8/8/4P3/3QK3 w - - 0 1").unwrap();
+        let moved = Position::from_str("3k4/3p4/8/8/8/8/4P3/4KQ2 b - - 0 1").unwrap();
+        let pawn_moved = Position::from_str("4k3/3p4/8/8/8/4P3/8/3QK3 w - - 0 1").unwrap();
+
+        assert_eq!(position.pawn_key, moved.pawn_key);
+        assert_ne!(position.pawn_key, pawn_moved.pawn_key);
+    }
+
+    #[test]
+    fn material_key_signature() {
+        let position = Position::from_str("8/8/3k4/8/8/8/1R6/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(
+            position.material_key,
+            Zobrist::from_material_signature("KRvK").unwrap()
+        );
+        assert_ne!(
+            position.material_key,
+            Zobrist::from_material_signature("KvKR").unwrap()
+        );
+        assert_eq!(
+            Zobrist::from_material_signature("KX"),
+            Err(Error::ParsePiece)
+        );
+    }
+
+    #[test]
+    fn material_key_capture_and_promotion() {
+        generate_lookup_tables();
+
+        let mut position = Position::from_str("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let capture_promotion = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| {
+                matches!(
+                    mv,
+                    Move::Standard {
+                        capture: Some(Role::Rook),
+                        promotion: Some(Role::Queen),
+                        ..
+                    }
+                )
+            })
+            .unwrap();
+
+        position.make_move(capture_promotion);
+
+        assert_eq!(
+            position.material_key,
+            Zobrist::from_material_signature("KQvK").unwrap()
+        );
+        assert_eq!(position.pawn_key, Zobrist(0));
+    }
+
+    /// Checks that the incremental update matches a full recompute for every position reachable
+    /// within `depth` plies.
+    fn assert_incremental_matches(position: &Position, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+
+        for mv in position.legal_moves() {
+            let mut next_position = *position;
+            next_position.make_move(mv);
+            assert_eq!(
+                next_position.zobrist,
+                Zobrist::from_position(&next_position),
+                "{} after {:?}",
+                position,
+                mv
+            );
+            assert_eq!(
+                next_position.pawn_key,
+                Zobrist::pawns_from_position(&next_position)
+            );
+            assert_eq!(
+                next_position.material_key,
+                Zobrist::material_from_position(&next_position)
+            );
+            assert_incremental_matches(&next_position, depth - 1);
+        }
+    }
+
+    #[test]
+    fn zobrist_incremental_perft_suite() {
+        generate_lookup_tables();
+
+        for fen in [
+            Position::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            let position = Position::from_str(fen).unwrap();
+            assert_incremental_matches(&position, 3);
+        }
     }
 
     #[test]
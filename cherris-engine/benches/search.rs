@@ -1,12 +1,15 @@
 use cherris_core::{Position, generate_lookup_tables};
 use cherris_engine::{
-    iterative_deepening::iterative_deepening, time_managment::TimeManagment,
-    transposition_table::TranspositionTable,
+    Extensions, SearchLimits, iterative_deepening::iterative_deepening,
+    time_managment::TimeManagment, transposition_table::TranspositionTable,
 };
 use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 use std::{
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64},
+    },
     time::Duration,
 };
 
@@ -16,15 +19,31 @@ pub fn search_benchmark(c: &mut Criterion) {
     let position = Position::new();
 
     let mut group = c.benchmark_group("search");
-    let max_depth = 7;
-    let max_nodes = u64::MAX;
-    let time_managment = TimeManagment::new(u128::MAX, 0, Some(40));
+    let search_limits = SearchLimits {
+        max_depth: 7,
+        max_nodes: u64::MAX,
+        search_moves: vec![],
+        multi_pv: 1,
+        time_managment: TimeManagment::new(u128::MAX, 0, Some(40)),
+        extensions: Extensions::default(),
+    };
 
     group.measurement_time(Duration::from_secs(10));
     group.bench_function("iterative deepening 7", |b| {
         b.iter_batched(
-            || Arc::new(Mutex::new(TranspositionTable::new(2_u64.pow(24)))),
-            |tt| iterative_deepening(position, max_depth, max_nodes, time_managment, tt),
+            || Arc::new(TranspositionTable::with_megabytes(64)),
+            |tt| {
+                iterative_deepening(
+                    position,
+                    &[],
+                    &search_limits,
+                    tt,
+                    Arc::new(AtomicBool::new(false)),
+                    Arc::new(AtomicU64::new(0)),
+                    0,
+                    |_| {},
+                )
+            },
             BatchSize::SmallInput,
         );
     });
@@ -32,11 +51,25 @@ pub fn search_benchmark(c: &mut Criterion) {
     let kiwi_pete =
         Position::from_str("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
             .unwrap();
-    let max_depth = 5;
+    let search_limits = SearchLimits {
+        max_depth: 5,
+        ..search_limits
+    };
     group.bench_function("iterative deepening kiwipete 5", |b| {
         b.iter_batched(
-            || Arc::new(Mutex::new(TranspositionTable::new(2_u64.pow(24)))),
-            |tt| iterative_deepening(kiwi_pete, max_depth, max_nodes, time_managment, tt),
+            || Arc::new(TranspositionTable::with_megabytes(64)),
+            |tt| {
+                iterative_deepening(
+                    kiwi_pete,
+                    &[],
+                    &search_limits,
+                    tt,
+                    Arc::new(AtomicBool::new(false)),
+                    Arc::new(AtomicU64::new(0)),
+                    0,
+                    |_| {},
+                )
+            },
             BatchSize::SmallInput,
         );
     });
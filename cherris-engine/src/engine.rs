@@ -1,33 +1,55 @@
 use std::{
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
 };
 
 use cherris_core::{
-    LAN, Move, Position, generate_lookup_tables,
-    uci::{UCIEngineCommand, UCIGoParams, UCIGuiCommand},
+    FenMode, LAN, Move, Position, Zobrist, generate_lookup_tables,
+    uci::{UCIEngineCommand, UCIGoParams, UCIGuiCommand, UCIOptionParams},
 };
 
-use crate::{Search, transposition_table::TranspositionTable};
+use crate::{
+    Extensions, Search, SearchOptions, time_managment::PonderHit,
+    transposition_table::TranspositionTable,
+};
 
-use self::engine_option::EngineOptions;
+use self::engine_option::{
+    CHECK_EXTENSION, CLEAR_HASH, EngineOptions, HASH, MULTI_PV, SINGULAR_EXTENSION, THREADS,
+};
 
-mod engine_option;
+pub mod engine_option;
 
 pub struct Engine {
     position: Position,
+    /// The zobrist keys of the positions before `position` in the current game.
+    history: Vec<Zobrist>,
     uci_search_params: UCIGoParams,
-    transposition_table: Arc<Mutex<TranspositionTable>>,
+    transposition_table: Arc<TranspositionTable>,
     options: EngineOptions,
+    stop: Arc<AtomicBool>,
+    ponder_hit: Arc<PonderHit>,
+    search_thread: Option<JoinHandle<()>>,
 }
 
 impl Engine {
     pub fn new() -> Engine {
+        let options = EngineOptions::new();
+
         Engine {
             position: Position::default(),
+            history: Vec::new(),
             uci_search_params: UCIGoParams::default(),
-            transposition_table: Arc::new(Mutex::new(TranspositionTable::new(2_u64.pow(24)))),
-            options: EngineOptions::new(),
+            transposition_table: Arc::new(TranspositionTable::with_megabytes(
+                options.spin(HASH) as u64
+            )),
+            options,
+            stop: Arc::new(AtomicBool::new(false)),
+            ponder_hit: Arc::new(PonderHit::new()),
+            search_thread: None,
         }
     }
 
@@ -47,31 +69,66 @@ impl Engine {
                         generate_lookup_tables();
                         self.send_command(UCIGuiCommand::IdName("cherris".to_string()));
                         self.send_command(UCIGuiCommand::IdAuthor("Johannes Thiel".to_string()));
-                        for option in self.options.0.iter() {
-                            self.send_command(UCIGuiCommand::Option(option.clone()));
+                        for option in self.options.uci_options() {
+                            self.send_command(UCIGuiCommand::Option(option));
                         }
                         self.send_command(UCIGuiCommand::UciOk);
                     }
                     UCIEngineCommand::Position(params) => {
-                        self.position = Position::from_str(&params.fen).unwrap();
+                        // An invalid position is ignored so the engine keeps a legal position.
+                        let position = match Position::from_fen(&params.fen, FenMode::Strict) {
+                            Ok(position) => position,
+                            Err(error) => {
+                                eprintln!("{}", error);
+                                input.clear();
+                                continue;
+                            }
+                        };
+                        self.position = position;
+                        self.history.clear();
                         for mv in params.moves {
-                            if let Ok(lan) = LAN::from_str(&mv) {
-                                if let Ok(mv) = Move::from_lan(&lan, &self.position) {
-                                    self.position.make_move(mv);
-                                }
+                            if let Ok(lan) = LAN::from_str(&mv)
+                                && let Ok(mv) = Move::from_lan(&lan, &self.position)
+                            {
+                                self.history.push(self.position.zobrist);
+                                self.position.make_move(mv);
                             }
                         }
                     }
                     UCIEngineCommand::Go(search_params) => {
+                        self.stop_search();
+                        self.stop.store(false, Ordering::Relaxed);
+                        self.ponder_hit = Arc::new(PonderHit::new());
+
                         self.uci_search_params = search_params;
-                        Search::run(
+                        self.search_thread = Some(Search::run(
                             self.position,
+                            self.history.clone(),
                             self.uci_search_params.clone(),
                             self.transposition_table.clone(),
-                        );
+                            self.stop.clone(),
+                            self.ponder_hit.clone(),
+                            SearchOptions {
+                                threads: self.options.spin(THREADS) as usize,
+                                multi_pv: self.options.spin(MULTI_PV) as usize,
+                                extensions: Extensions {
+                                    check: self.options.check(CHECK_EXTENSION),
+                                    singular: self.options.check(SINGULAR_EXTENSION),
+                                },
+                            },
+                        ));
+                    }
+                    UCIEngineCommand::Stop => self.stop_search(),
+                    UCIEngineCommand::PonderHit => self.ponder_hit.hit(),
+                    UCIEngineCommand::SetOption(params) => {
+                        self.stop_search();
+                        self.set_option(&params);
                     }
                     UCIEngineCommand::IsReady => self.send_command(UCIGuiCommand::ReadyOk),
-                    UCIEngineCommand::Quit => break,
+                    UCIEngineCommand::Quit => {
+                        self.stop_search();
+                        break;
+                    }
                     _ => {}
                 }
             }
@@ -80,6 +137,36 @@ impl Engine {
         }
     }
 
+    /// Signals a running search to stop and waits until it has sent its best move.
+    fn stop_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            search_thread.join().expect("Search thread panicked");
+        }
+    }
+
+    /// Applies an option sent by the GUI. Invalid options are reported on stderr and ignored.
+    fn set_option(&mut self, params: &UCIOptionParams) {
+        let id = match self.options.set(params) {
+            Ok(option) => option.id,
+            Err(err) => {
+                eprintln!("{}", err);
+                return;
+            }
+        };
+
+        match id {
+            HASH => {
+                let megabytes = self.options.spin(HASH) as u64;
+                // Free the old table first so both never have to fit into memory at once.
+                self.transposition_table = Arc::new(TranspositionTable::new(1));
+                self.transposition_table = Arc::new(TranspositionTable::with_megabytes(megabytes));
+            }
+            CLEAR_HASH => self.transposition_table.clear(),
+            _ => {}
+        }
+    }
+
     fn send_command(&self, command: UCIGuiCommand) {
         print!("{}", command);
     }
@@ -1,19 +1,405 @@
-use cherris_core::uci::{UCIOption, UCIOptionType};
+use std::fmt::Display;
 
+use cherris_core::uci::{UCIOption, UCIOptionParams, UCIOptionType};
+
+/// The name of the option that sets the size of the transposition table in megabytes.
+pub const HASH: &str = "Hash";
+/// The name of the option that tells the engine whether the GUI allows pondering.
+pub const PONDER: &str = "Ponder";
+/// The name of the option that sets the number of search threads.
+pub const THREADS: &str = "Threads";
+/// The name of the option that sets the number of lines the engine reports.
+pub const MULTI_PV: &str = "MultiPV";
+/// The name of the button that clears the transposition table.
+pub const CLEAR_HASH: &str = "Clear Hash";
+/// The name of the option that turns the extension of checking moves on or off.
+pub const CHECK_EXTENSION: &str = "CheckExtension";
+/// The name of the option that turns the extension of singular moves on or off.
+pub const SINGULAR_EXTENSION: &str = "SingularExtension";
+
+/// The type of an `EngineOption` together with its constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineOptionType {
+    Check,
+    Spin { min: i64, max: i64 },
+    Combo { vars: Vec<String> },
+    Button,
+    String,
+}
+
+/// The value of an `EngineOption`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineOptionValue {
+    Check(bool),
+    Spin(i64),
+    Combo(String),
+    Button,
+    String(String),
+}
+
+/// The errors that can occur when the GUI sets an option.
+#[derive(Debug, PartialEq)]
+pub enum EngineOptionError {
+    /// There is no option with the given name.
+    UnknownOption(String),
+    /// The option needs a value but none was sent.
+    MissingValue(String),
+    /// The value doesn't fit the type of the option.
+    InvalidValue { id: String, value: String },
+    /// The value of a spin option is outside of its min and max.
+    OutOfRange {
+        id: String,
+        value: i64,
+        min: i64,
+        max: i64,
+    },
+}
+
+impl Display for EngineOptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineOptionError::UnknownOption(id) => write!(f, "unknown option {}", id),
+            EngineOptionError::MissingValue(id) => write!(f, "option {} needs a value", id),
+            EngineOptionError::InvalidValue { id, value } => {
+                write!(f, "invalid value {} for option {}", value, id)
+            }
+            EngineOptionError::OutOfRange {
+                id,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "value {} for option {} is not between {} and {}",
+                value, id, min, max
+            ),
+        }
+    }
+}
+
+/// An option of the engine that can be changed by the GUI with `setoption`.
+#[derive(Clone, Debug)]
+pub struct EngineOption {
+    pub id: &'static str,
+    pub option_type: EngineOptionType,
+    pub default: EngineOptionValue,
+    pub value: EngineOptionValue,
+}
+
+impl EngineOption {
+    pub fn check(id: &'static str, default: bool) -> EngineOption {
+        EngineOption::new(
+            id,
+            EngineOptionType::Check,
+            EngineOptionValue::Check(default),
+        )
+    }
+
+    pub fn spin(id: &'static str, default: i64, min: i64, max: i64) -> EngineOption {
+        EngineOption::new(
+            id,
+            EngineOptionType::Spin { min, max },
+            EngineOptionValue::Spin(default),
+        )
+    }
+
+    pub fn combo(id: &'static str, default: &str, vars: &[&str]) -> EngineOption {
+        EngineOption::new(
+            id,
+            EngineOptionType::Combo {
+                vars: vars.iter().map(|var| var.to_string()).collect(),
+            },
+            EngineOptionValue::Combo(default.to_string()),
+        )
+    }
+
+    pub fn button(id: &'static str) -> EngineOption {
+        EngineOption::new(id, EngineOptionType::Button, EngineOptionValue::Button)
+    }
+
+    pub fn string(id: &'static str, default: &str) -> EngineOption {
+        EngineOption::new(
+            id,
+            EngineOptionType::String,
+            EngineOptionValue::String(default.to_string()),
+        )
+    }
+
+    fn new(
+        id: &'static str,
+        option_type: EngineOptionType,
+        default: EngineOptionValue,
+    ) -> EngineOption {
+        EngineOption {
+            id,
+            option_type,
+            value: default.clone(),
+            default,
+        }
+    }
+
+    /// Parses and validates a value sent by the GUI for this option.
+    pub fn parse(&self, value: Option<&str>) -> Result<EngineOptionValue, EngineOptionError> {
+        let value = match (&self.option_type, value) {
+            (EngineOptionType::Button, _) => return Ok(EngineOptionValue::Button),
+            (EngineOptionType::String, None) => {
+                return Ok(EngineOptionValue::String(String::new()));
+            }
+            (_, None) => return Err(EngineOptionError::MissingValue(self.id.to_string())),
+            (_, Some(value)) => value.trim(),
+        };
+
+        let invalid_value = || EngineOptionError::InvalidValue {
+            id: self.id.to_string(),
+            value: value.to_string(),
+        };
+
+        match &self.option_type {
+            EngineOptionType::Check => match value.to_lowercase().as_str() {
+                "true" => Ok(EngineOptionValue::Check(true)),
+                "false" => Ok(EngineOptionValue::Check(false)),
+                _ => Err(invalid_value()),
+            },
+            EngineOptionType::Spin { min, max } => {
+                let spin = value.parse::<i64>().map_err(|_| invalid_value())?;
+                if spin < *min || spin > *max {
+                    return Err(EngineOptionError::OutOfRange {
+                        id: self.id.to_string(),
+                        value: spin,
+                        min: *min,
+                        max: *max,
+                    });
+                }
+
+                Ok(EngineOptionValue::Spin(spin))
+            }
+            EngineOptionType::Combo { vars } => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| EngineOptionValue::Combo(var.clone()))
+                .ok_or_else(invalid_value),
+            EngineOptionType::String => Ok(EngineOptionValue::String(value.to_string())),
+            EngineOptionType::Button => unreachable!(),
+        }
+    }
+}
+
+impl From<&EngineOption> for UCIOption {
+    fn from(value: &EngineOption) -> Self {
+        let (option_type, min, max, var) = match &value.option_type {
+            EngineOptionType::Check => (UCIOptionType::Check, None, None, vec![]),
+            EngineOptionType::Spin { min, max } => (
+                UCIOptionType::Spin,
+                Some(min.to_string()),
+                Some(max.to_string()),
+                vec![],
+            ),
+            EngineOptionType::Combo { vars } => (UCIOptionType::Combo, None, None, vars.clone()),
+            EngineOptionType::Button => (UCIOptionType::Button, None, None, vec![]),
+            EngineOptionType::String => (UCIOptionType::String, None, None, vec![]),
+        };
+
+        let default = match &value.default {
+            EngineOptionValue::Check(check) => Some(check.to_string()),
+            EngineOptionValue::Spin(spin) => Some(spin.to_string()),
+            EngineOptionValue::Combo(combo) => Some(combo.clone()),
+            EngineOptionValue::Button => None,
+            EngineOptionValue::String(string) if string.is_empty() => Some("<empty>".to_string()),
+            EngineOptionValue::String(string) => Some(string.clone()),
+        };
+
+        UCIOption {
+            id: value.id.to_string(),
+            option_type,
+            default,
+            min,
+            max,
+            var,
+        }
+    }
+}
+
+/// All options the engine supports.
 #[derive(Clone)]
-pub struct EngineOptions(pub [UCIOption; 1]);
+pub struct EngineOptions(pub Vec<EngineOption>);
 
 impl EngineOptions {
     pub fn new() -> EngineOptions {
-        let hash_option = UCIOption {
-            id: "Hash".to_string(),
-            option_type: UCIOptionType::Spin,
-            default: Some("64".to_string()),
-            min: Some("32".to_string()),
-            max: Some("256".to_string()),
-            var: vec![],
-        };
+        EngineOptions(vec![
+            EngineOption::spin(HASH, 64, 1, 4096),
+            EngineOption::button(CLEAR_HASH),
+            EngineOption::spin(THREADS, 1, 1, 256),
+            EngineOption::spin(MULTI_PV, 1, 1, 256),
+            EngineOption::check(PONDER, false),
+            EngineOption::check(CHECK_EXTENSION, true),
+            EngineOption::check(SINGULAR_EXTENSION, true),
+        ])
+    }
+
+    /// Returns the option with the given id. Option ids are not case sensitive.
+    pub fn get(&self, id: &str) -> Option<&EngineOption> {
+        self.0
+            .iter()
+            .find(|option| option.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Returns the value of a spin option.
+    /// Panics if there is no spin option with the given id.
+    pub fn spin(&self, id: &str) -> i64 {
+        match self.get(id).map(|option| &option.value) {
+            Some(EngineOptionValue::Spin(spin)) => *spin,
+            _ => panic!("There is no spin option {}", id),
+        }
+    }
+
+    /// Returns the value of a check option.
+    /// Panics if there is no check option with the given id.
+    pub fn check(&self, id: &str) -> bool {
+        match self.get(id).map(|option| &option.value) {
+            Some(EngineOptionValue::Check(check)) => *check,
+            _ => panic!("There is no check option {}", id),
+        }
+    }
+
+    /// Validates and stores the value the GUI sent for an option. Returns the option that was
+    /// changed so the engine can react to it.
+    pub fn set(&mut self, params: &UCIOptionParams) -> Result<&EngineOption, EngineOptionError> {
+        let option = self
+            .0
+            .iter_mut()
+            .find(|option| option.id.eq_ignore_ascii_case(params.id.trim()))
+            .ok_or_else(|| EngineOptionError::UnknownOption(params.id.clone()))?;
+
+        option.value = option.parse(params.value.as_deref())?;
+
+        Ok(option)
+    }
+
+    /// Returns an iterator over all options in the format that is sent to the GUI.
+    pub fn uci_options(&self) -> impl Iterator<Item = UCIOption> + '_ {
+        self.0.iter().map(UCIOption::from)
+    }
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(id: &str, value: Option<&str>) -> UCIOptionParams {
+        UCIOptionParams {
+            id: id.to_string(),
+            value: value.map(|value| value.to_string()),
+        }
+    }
+
+    #[test]
+    fn set_spin() {
+        let mut options = EngineOptions::new();
+
+        options.set(&params("Hash", Some("128"))).unwrap();
+
+        assert_eq!(options.spin(HASH), 128);
+    }
+
+    #[test]
+    fn set_spin_out_of_range() {
+        let mut options = EngineOptions::new();
+
+        let result = options.set(&params("Hash", Some("0")));
+
+        assert_eq!(
+            result.unwrap_err(),
+            EngineOptionError::OutOfRange {
+                id: HASH.to_string(),
+                value: 0,
+                min: 1,
+                max: 4096
+            }
+        );
+        assert_eq!(options.spin(HASH), 64);
+    }
+
+    #[test]
+    fn set_spin_invalid() {
+        let mut options = EngineOptions::new();
+
+        let result = options.set(&params("Hash", Some("big")));
+
+        assert_eq!(
+            result.unwrap_err(),
+            EngineOptionError::InvalidValue {
+                id: HASH.to_string(),
+                value: "big".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn set_case_insensitive() {
+        let mut options = EngineOptions::new();
+
+        options.set(&params("ponder", Some("TRUE"))).unwrap();
+
+        assert!(options.check(PONDER));
+    }
+
+    #[test]
+    fn set_button() {
+        let mut options = EngineOptions::new();
+
+        let option = options.set(&params("Clear Hash", None)).unwrap();
+
+        assert_eq!(option.id, CLEAR_HASH);
+    }
+
+    #[test]
+    fn set_missing_value() {
+        let mut options = EngineOptions::new();
+
+        let result = options.set(&params("Hash", None));
+
+        assert_eq!(
+            result.unwrap_err(),
+            EngineOptionError::MissingValue(HASH.to_string())
+        );
+    }
+
+    #[test]
+    fn set_unknown() {
+        let mut options = EngineOptions::new();
+
+        let result = options.set(&params("Contempt", Some("10")));
+
+        assert_eq!(
+            result.unwrap_err(),
+            EngineOptionError::UnknownOption("Contempt".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_combo() {
+        let option = EngineOption::combo("Style", "Normal", &["Solid", "Normal", "Risky"]);
+
+        assert_eq!(
+            option.parse(Some("risky")),
+            Ok(EngineOptionValue::Combo("Risky".to_string()))
+        );
+        assert!(option.parse(Some("Crazy")).is_err());
+    }
+
+    #[test]
+    fn to_uci_option() {
+        let option = EngineOption::spin(HASH, 64, 1, 4096);
 
-        EngineOptions([hash_option])
+        assert_eq!(
+            UCIOption::from(&option).to_string(),
+            "name Hash type spin default 64 min 1 max 4096"
+        );
     }
 }
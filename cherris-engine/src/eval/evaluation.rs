@@ -1,15 +1,10 @@
-use core::f64;
-use std::{
-    i16,
-    ops::{AddAssign, Neg},
-    u8,
-};
+use std::ops::{AddAssign, Neg};
 
 use cherris_core::uci::UCIScore;
 
 const CHECKMATE_BASE: i16 = -32000;
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Evaluation(i16);
 
 impl Evaluation {
@@ -36,8 +31,28 @@ impl Evaluation {
         self.0
     }
 
+    /// Returns the number of plies until the mate, no matter which side is mated.
     pub fn mate_in(&self) -> i16 {
-        self.0.abs() + CHECKMATE_BASE
+        -CHECKMATE_BASE - self.0.abs()
+    }
+
+    /// Converts a mate score counted from the root to one counted from the node at `ply`, so it
+    /// stays correct when the node is reached again at a different ply.
+    pub fn to_transposition_table(self, ply: u8) -> Evaluation {
+        match self.0 {
+            eval if eval >= -CHECKMATE_BASE - 100 => Evaluation(eval + ply as i16),
+            eval if eval <= CHECKMATE_BASE + 100 => Evaluation(eval - ply as i16),
+            eval => Evaluation(eval),
+        }
+    }
+
+    /// Converts a mate score counted from the node at `ply` back to one counted from the root.
+    pub fn from_transposition_table(self, ply: u8) -> Evaluation {
+        match self.0 {
+            eval if eval >= -CHECKMATE_BASE - 100 => Evaluation(eval - ply as i16),
+            eval if eval <= CHECKMATE_BASE + 100 => Evaluation(eval + ply as i16),
+            eval => Evaluation(eval),
+        }
     }
 }
 
@@ -58,9 +73,55 @@ impl Neg for Evaluation {
 impl From<Evaluation> for UCIScore {
     fn from(value: Evaluation) -> Self {
         if value.is_checkmate() {
-            UCIScore::Mate((value.mate_in() as f64 / 2.0).ceil() as i16)
+            // UCI counts mates in moves, negative if the engine is mated.
+            let moves = (value.mate_in() + 1) / 2;
+            if value.0 > 0 {
+                UCIScore::Mate(moves)
+            } else {
+                UCIScore::Mate(-moves)
+            }
         } else {
             UCIScore::Centipawns(value.centipawns())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uci_mate_score() {
+        let uci_score = |eval: Evaluation| UCIScore::from(eval).to_string();
+
+        assert_eq!(uci_score(-Evaluation::new_mate_in(5)), "mate 3");
+        assert_eq!(uci_score(Evaluation::new_mate_in(4)), "mate -2");
+        assert_eq!(uci_score(Evaluation::new(-45)), "cp -45");
+    }
+
+    #[test]
+    fn transposition_table_mate_scores() {
+        // Mated at ply 7 is mated 4 plies after a node at ply 3, which is at ply 9 from a node at
+        // ply 5.
+        let mated = Evaluation::new_mate_in(7);
+        assert_eq!(mated.to_transposition_table(3), Evaluation::new_mate_in(4));
+        assert_eq!(
+            mated.to_transposition_table(3).from_transposition_table(5),
+            Evaluation::new_mate_in(9)
+        );
+
+        let mating = -Evaluation::new_mate_in(7);
+        assert_eq!(
+            mating.to_transposition_table(3),
+            -Evaluation::new_mate_in(4)
+        );
+        assert_eq!(
+            mating.to_transposition_table(3).from_transposition_table(3),
+            mating
+        );
+
+        let eval = Evaluation::new(250);
+        assert_eq!(eval.to_transposition_table(3), eval);
+        assert_eq!(eval.from_transposition_table(3), eval);
+    }
+}
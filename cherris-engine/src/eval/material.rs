@@ -36,7 +36,7 @@ mod tests {
     #[test]
     fn eval_material_two_pawns_down() {
         let position =
-            Position::from_str("1k1r2p1/5p2/2b1n1Q1/3p4/2p1N3/1q1BPP2/4K3/5R2 w - - 0 1").unwrap();
+            Position::from_str("1k1r4/5pp1/2b1n1Q1/3p4/2p1N3/1q1BPP2/4K3/5R2 w - - 0 1").unwrap();
 
         let result = eval_material(&position);
 
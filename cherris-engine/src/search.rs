@@ -1,20 +1,31 @@
 use std::{
-    sync::{Arc, Mutex},
-    thread,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
 use cherris_core::{
-    Color, Move, Position,
+    Color, LAN, Move, Position, Zobrist,
     uci::{UCIGoParams, UCIGuiCommand},
 };
 
-use crate::{iterative_deepening::iterative_deepening, time_managment::TimeManagment};
+use crate::{
+    iterative_deepening::iterative_deepening,
+    time_managment::{PonderHit, TimeManagment},
+};
 
-use self::transposition_table::TranspositionTable;
+use self::{
+    history::HistoryTable, killer_moves::KillerMoves, transposition_table::TranspositionTable,
+};
 
 pub mod alpha_beta;
 pub mod history;
 pub mod iterative_deepening;
+pub mod killer_moves;
 pub mod move_sort;
 pub mod quiescence;
 pub mod time_managment;
@@ -22,27 +33,166 @@ pub mod transposition_table;
 
 const DEFAULT_MAX_DEPTH: u8 = 10;
 
+/// The maximum depth the engine will ever search to.
+pub const MAX_DEPTH: u8 = 64;
+
+/// The number of nodes after which the search checks if its time is up.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// The moves that are searched one ply deeper than the others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Extensions {
+    /// Extends moves that give check.
+    pub check: bool,
+    /// Extends the move from the transposition table if all other moves are clearly worse.
+    pub singular: bool,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Extensions {
+            check: true,
+            singular: true,
+        }
+    }
+}
+
+/// The settings of a search that are set with UCI options instead of the `go` command.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions {
+    /// The number of threads that search in parallel.
+    pub threads: usize,
+    /// The number of best lines reported to the GUI.
+    pub multi_pv: usize,
+    pub extensions: Extensions,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            threads: 1,
+            multi_pv: 1,
+            extensions: Extensions::default(),
+        }
+    }
+}
+
 pub struct SearchData {
     pub nodes: u64,
     pub max_nodes: u64,
     pub pv: Vec<Move>,
-    pub transposition_table: Arc<Mutex<TranspositionTable>>,
+    pub transposition_table: Arc<TranspositionTable>,
     pub current_depth: u8,
     pub selective_depth: u8,
+    pub search_moves: Vec<Move>,
+    pub time_managment: TimeManagment,
+    pub stop: Arc<AtomicBool>,
+    pub stopped: bool,
+    /// The number of nodes searched by all threads together.
+    pub total_nodes: Arc<AtomicU64>,
+    /// The part of `nodes` that was already added to `total_nodes`.
+    pub reported_nodes: u64,
+    /// The zobrist keys of all positions before the current one, from the moves of the game and
+    /// the current search path.
+    pub history: Vec<Zobrist>,
+    /// Whether the last move on the current search path was a null move.
+    pub null_move: bool,
+    /// The length of `history` right after the last null move on the current search path. The
+    /// positions before a null move can't be repeated after it.
+    pub null_move_history: usize,
+    pub history_table: HistoryTable,
+    pub killer_moves: KillerMoves,
+    pub extensions: Extensions,
+    /// The move that is left out at the current node while checking if it is singular.
+    pub excluded_move: Option<Move>,
 }
 
-pub struct Search {}
+impl SearchData {
+    /// Checks if the search has to stop as soon as possible, either because the GUI sent `stop` or
+    /// because the time for this move is up.
+    #[inline]
+    pub fn is_stopped(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) {
+            self.report_nodes();
+            self.stopped = self.time_managment.is_time_up();
+        }
 
-impl Search {
-    pub fn run(
-        position: Position,
-        search_params: UCIGoParams,
-        transposition_table: Arc<Mutex<TranspositionTable>>,
-    ) {
-        thread::spawn(move || {
-            let max_depth = search_params.depth.unwrap_or(DEFAULT_MAX_DEPTH);
-            let max_nodes = search_params.nodes.unwrap_or(u64::MAX);
+        self.stopped || self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Checks if the position is a draw by the fifty-move rule or because it repeats an earlier
+    /// position. A single repetition is enough, as the side that repeated could do it again.
+    /// A checkmate on the move that reaches the fifty-move limit still wins.
+    pub fn is_draw(&self, position: &Position) -> bool {
+        if position.halfmove_clock >= 100 {
+            return !position.is_checkmate();
+        }
+
+        // Only positions with the same color to move since the last capture, pawn move or null move
+        // can be repeated.
+        let plies_since_null_move = self.history.len() - self.null_move_history;
+        self.history
+            .iter()
+            .rev()
+            .take((position.halfmove_clock as usize).min(plies_since_null_move))
+            .skip(1)
+            .step_by(2)
+            .any(|zobrist| *zobrist == position.zobrist)
+    }
 
+    /// Adds the nodes searched since the last call to the node count shared by all threads.
+    pub fn report_nodes(&mut self) {
+        self.total_nodes
+            .fetch_add(self.nodes - self.reported_nodes, Ordering::Relaxed);
+        self.reported_nodes = self.nodes;
+    }
+}
+
+/// The limits of a search as requested by the GUI with the `go` command.
+#[derive(Clone)]
+pub struct SearchLimits {
+    pub max_depth: u8,
+    pub max_nodes: u64,
+    /// The moves the search is restricted to at the root. An empty list allows all moves.
+    pub search_moves: Vec<Move>,
+    /// The number of best lines to search at the root.
+    pub multi_pv: usize,
+    pub time_managment: TimeManagment,
+    pub extensions: Extensions,
+}
+
+impl SearchLimits {
+    pub fn new(position: &Position, search_params: &UCIGoParams) -> SearchLimits {
+        let mut max_depth = match search_params.depth {
+            Some(depth) => depth.min(MAX_DEPTH),
+            None if search_params.infinite || search_params.movetime.is_some() => MAX_DEPTH,
+            None => DEFAULT_MAX_DEPTH,
+        };
+
+        // A mate in n moves is found after at most 2n - 1 plies.
+        if let Some(mate) = search_params.mate {
+            let mate_depth = (mate * 2).saturating_sub(1).clamp(1, MAX_DEPTH.into()) as u8;
+            if search_params.depth.is_none() {
+                max_depth = mate_depth;
+            } else {
+                max_depth = max_depth.min(mate_depth);
+            }
+        }
+
+        let max_nodes = search_params.nodes.unwrap_or(u64::MAX);
+
+        let search_moves = search_params
+            .search_moves
+            .iter()
+            .filter_map(|mv| LAN::from_str(mv).ok())
+            .filter_map(|lan| Move::from_lan(&lan, position).ok())
+            .collect();
+
+        let time_managment = if search_params.infinite {
+            TimeManagment::infinite()
+        } else if let Some(movetime) = search_params.movetime {
+            TimeManagment::fixed(movetime.into())
+        } else {
             let (time, increment) = match position.color_to_move {
                 Color::White => (
                     search_params.w_time.unwrap_or(u128::MAX),
@@ -54,24 +204,270 @@ impl Search {
                 ),
             };
 
-            let time_managment = TimeManagment::new(time, increment, search_params.moves_to_go);
-            let best_move = iterative_deepening(
+            TimeManagment::new(time, increment, search_params.moves_to_go)
+        };
+
+        SearchLimits {
+            max_depth,
+            max_nodes,
+            search_moves,
+            multi_pv: 1,
+            time_managment,
+            extensions: Extensions::default(),
+        }
+    }
+}
+
+pub struct Search {}
+
+impl Search {
+    /// Starts a search on a new thread. `history` contains the zobrist keys of the positions
+    /// before `position` in the game and is used to detect repetitions. The search can be
+    /// cancelled at any time by setting `stop`, in which case the best move of the last completed
+    /// iteration is sent to the GUI.
+    /// A search started with `go ponder` runs without time limits until `ponder_hit` is hit.
+    /// With more than one thread, helper threads search the same position and share their results
+    /// through the transposition table (Lazy SMP).
+    pub fn run(
+        position: Position,
+        history: Vec<Zobrist>,
+        search_params: UCIGoParams,
+        transposition_table: Arc<TranspositionTable>,
+        stop: Arc<AtomicBool>,
+        ponder_hit: Arc<PonderHit>,
+        search_options: SearchOptions,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
+            let mut search_limits = SearchLimits::new(&position, &search_params);
+            search_limits.multi_pv = search_options.multi_pv;
+            search_limits.extensions = search_options.extensions;
+            if search_params.ponder {
+                search_limits.time_managment =
+                    search_limits.time_managment.ponder(ponder_hit.clone());
+            }
+
+            let total_nodes = Arc::new(AtomicU64::new(0));
+
+            // The helpers only stop once the main thread is done, which decides when to stop.
+            let helper_stop = Arc::new(AtomicBool::new(false));
+            let helper_limits = SearchLimits {
+                max_depth: MAX_DEPTH,
+                time_managment: TimeManagment::infinite(),
+                ..search_limits.clone()
+            };
+            let helpers: Vec<_> = (1..search_options.threads.max(1))
+                .map(|thread_id| {
+                    let helper_limits = helper_limits.clone();
+                    let transposition_table = transposition_table.clone();
+                    let helper_stop = helper_stop.clone();
+                    let total_nodes = total_nodes.clone();
+                    let history = history.clone();
+                    thread::spawn(move || {
+                        iterative_deepening(
+                            position,
+                            &history,
+                            &helper_limits,
+                            transposition_table,
+                            helper_stop,
+                            total_nodes,
+                            thread_id,
+                            |_| {},
+                        );
+                    })
+                })
+                .collect();
+
+            let pv = iterative_deepening(
                 position,
-                max_depth,
-                max_nodes,
-                time_managment,
-                transposition_table,
+                &history,
+                &search_limits,
+                transposition_table.clone(),
+                stop.clone(),
+                total_nodes,
+                0,
+                |command| print!("{}", command),
             );
 
-            let best_move = match (best_move, position.color_to_move) {
-                (Move::CastleShort, Color::White) => "e1g1".to_string(),
-                (Move::CastleShort, Color::Black) => "e8g8".to_string(),
-                (Move::CastleLong, Color::White) => "e1c1".to_string(),
-                (Move::CastleLong, Color::Black) => "e8c8".to_string(),
-                _ => best_move.to_string(),
-            };
+            helper_stop.store(true, Ordering::Relaxed);
+            for helper in helpers {
+                helper.join().expect("Helper thread panicked");
+            }
+
+            // In infinite mode and while pondering the best move may only be sent after the GUI
+            // sent `stop` or `ponderhit`.
+            while !stop.load(Ordering::Relaxed)
+                && (search_params.infinite || (search_params.ponder && !ponder_hit.is_hit()))
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let best_move = pv[0];
+            let mut next_position = position;
+            next_position.make_move(best_move);
+
+            // The pv can be cut short by a transposition table hit, in which case the table may
+            // still know the reply.
+            let ponder_move = pv.get(1).copied().or_else(|| {
+                transposition_table
+                    .get_entry(next_position.zobrist)
+                    .map(|entry| entry.chess_move)
+                    .filter(|mv| next_position.legal_moves().contains(mv))
+            });
+
+            print!(
+                "{}",
+                UCIGuiCommand::BestMove {
+                    chess_move: best_move.to_uci(position.color_to_move),
+                    ponder: ponder_move.map(|mv| mv.to_uci(next_position.color_to_move)),
+                }
+            );
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use cherris_core::{Role, Square, generate_lookup_tables};
+
+    use crate::transposition_table::TranspositionTable;
+
+    use super::*;
+
+    #[test]
+    fn limits_mate() {
+        let search_params = UCIGoParams {
+            mate: Some(3),
+            ..Default::default()
+        };
+
+        let search_limits = SearchLimits::new(&Position::default(), &search_params);
+
+        assert_eq!(search_limits.max_depth, 5);
+    }
+
+    #[test]
+    fn limits_movetime() {
+        let search_params = UCIGoParams {
+            movetime: Some(1000),
+            ..Default::default()
+        };
+
+        let search_limits = SearchLimits::new(&Position::default(), &search_params);
+
+        assert_eq!(search_limits.max_depth, MAX_DEPTH);
+        assert!(!search_limits.time_managment.is_time_up());
+    }
+
+    #[test]
+    fn limits_search_moves() {
+        generate_lookup_tables();
+
+        let search_params = UCIGoParams {
+            search_moves: vec!["e2e4".to_string(), "e2e5".to_string()],
+            ..Default::default()
+        };
+
+        let search_limits = SearchLimits::new(&Position::default(), &search_params);
+
+        assert_eq!(
+            search_limits.search_moves,
+            vec![Move::Standard {
+                from: Square::E2,
+                to: Square::E4,
+                role: Role::Pawn,
+                capture: None,
+                promotion: None,
+                en_passant_square: Some(Square::E3),
+            }]
+        );
+    }
+
+    fn search_data(history: Vec<Zobrist>) -> SearchData {
+        SearchData {
+            nodes: 0,
+            max_nodes: u64::MAX,
+            pv: vec![],
+            transposition_table: Arc::new(TranspositionTable::new(1)),
+            current_depth: 0,
+            selective_depth: 0,
+            search_moves: vec![],
+            time_managment: TimeManagment::infinite(),
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+            total_nodes: Arc::new(AtomicU64::new(0)),
+            reported_nodes: 0,
+            history,
+            null_move: false,
+            null_move_history: 0,
+            history_table: HistoryTable::new(),
+            killer_moves: KillerMoves::new(),
+            extensions: Extensions::default(),
+            excluded_move: None,
+        }
+    }
+
+    #[test]
+    fn draw_by_repetition() {
+        generate_lookup_tables();
+
+        let mut position = Position::default();
+        let mut history = vec![];
+        for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            history.push(position.zobrist);
+            let lan = LAN::from_str(mv).unwrap();
+            position.make_move(Move::from_lan(&lan, &position).unwrap());
+        }
+
+        assert!(search_data(history.clone()).is_draw(&position));
+        assert!(!search_data(history[..3].to_vec()).is_draw(&position));
+    }
+
+    #[test]
+    fn draw_by_fifty_move_rule() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80").unwrap();
+
+        assert!(search_data(vec![]).is_draw(&position));
+    }
+
+    #[test]
+    fn no_repetition_across_null_move() {
+        generate_lookup_tables();
+
+        let mut position = Position::default();
+        let mut search_data = search_data(vec![]);
+        for _ in 0..2 {
+            search_data.history.push(position.zobrist);
+            search_data.null_move_history = search_data.history.len();
+            position.make_null_move();
+        }
+
+        assert_eq!(position.zobrist, search_data.history[0]);
+        assert!(!search_data.is_draw(&position));
+    }
+
+    #[test]
+    fn fifty_move_rule_after_null_move() {
+        generate_lookup_tables();
+
+        let mut position = Position::from_str("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+        let mut search_data = search_data(vec![]);
+        search_data.history.push(position.zobrist);
+        search_data.null_move_history = search_data.history.len();
+        position.make_null_move();
+
+        assert!(search_data.is_draw(&position));
+    }
+
+    #[test]
+    fn checkmate_beats_fifty_move_rule() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 100 80").unwrap();
 
-            print!("{}", UCIGuiCommand::BestMove(best_move));
-        });
+        assert!(!search_data(vec![]).is_draw(&position));
     }
 }
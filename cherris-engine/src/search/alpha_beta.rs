@@ -1,33 +1,98 @@
-use cherris_core::{Move, MoveList, Position, generate_moves};
+use std::{mem, sync::LazyLock};
+
+use cherris_core::{Color, Move, MoveList, Position, generate_moves};
 
 use crate::{
-    SearchData,
+    MAX_DEPTH, SearchData, eval,
     evaluation::Evaluation,
-    move_sort::sort_moves,
+    move_sort::{is_quiet, sort_moves},
     quiescence::quiescence,
     transposition_table::{TranspositionEntry, TranspositionEntryType},
 };
 
+/// Extensions are only added up to this ply, so checks can't extend a line forever.
+const MAX_EXTENSION_PLY: u8 = MAX_DEPTH;
+
+/// The minimum depth at which the move from the transposition table is checked for being singular.
+const SINGULAR_MIN_DEPTH: u8 = 6;
+/// How much shallower the transposition table entry may be than the current search.
+const SINGULAR_TT_DEPTH_MARGIN: u8 = 3;
+/// How far below the score of the transposition table all other moves have to stay, per ply.
+const SINGULAR_MARGIN: i16 = 2;
+
+/// The minimum depth at which null-move pruning is tried.
+const NULL_MOVE_MIN_DEPTH: u8 = 3;
+/// The number of plies the search after a null move is reduced by, in addition to the null move.
+const NULL_MOVE_REDUCTION: u8 = 2;
+
+/// The minimum remaining depth at which late moves are reduced.
+const LMR_MIN_DEPTH: u8 = 3;
+/// The number of moves that are always searched at full depth.
+const LMR_MIN_MOVE_INDEX: usize = 3;
+/// The reduction every late move gets, in plies.
+const LMR_BASE: f64 = 0.75;
+/// Scales down the growth of the reduction with depth and move index.
+const LMR_DIVISOR: f64 = 2.25;
+
+/// The number of plies a late quiet move is reduced by, indexed by the remaining depth and the
+/// index of the move in the sorted move list.
+static LMR_TABLE: LazyLock<[[u8; 64]; 64]> = LazyLock::new(|| {
+    let mut table = [[0; 64]; 64];
+    for (depth, reductions) in table.iter_mut().enumerate().skip(1) {
+        for (move_index, reduction) in reductions.iter_mut().enumerate().skip(1) {
+            let ln_depth = (depth as f64).ln();
+            let ln_move_index = (move_index as f64).ln();
+            *reduction = (LMR_BASE + ln_depth * ln_move_index / LMR_DIVISOR) as u8;
+        }
+    }
+
+    table
+});
+
+fn late_move_reduction(depth: u8, move_index: usize) -> u8 {
+    LMR_TABLE[usize::from(depth).min(63)][move_index.min(63)]
+}
+
 pub fn alpha_beta(
     alpha: Evaluation,
     beta: Evaluation,
     depth: u8,
     pv: &mut Vec<Move>,
-    position: &Position,
+    position: &mut Position,
     search_data: &mut SearchData,
 ) -> Evaluation {
-    let is_root = search_data.nodes == 0;
+    let is_root = search_data.current_depth == 0;
+    let ply = search_data.current_depth;
+    // Only set for the node that checks if its move from the transposition table is singular.
+    let excluded_move = search_data.excluded_move.take();
 
-    let tt_table = search_data.transposition_table.lock().unwrap();
-    let (tt_move, tt_value) = tt_table.get(position, alpha, beta, depth);
-    drop(tt_table);
+    if !is_root && search_data.is_draw(position) {
+        return Evaluation::DRAW;
+    }
 
-    if let Some(tt_value) = tt_value {
-        if !is_root {
-            return tt_value;
+    // No line through this node can end in a faster mate than one found closer to the root, so
+    // the window is narrowed to the mate scores still possible at this ply.
+    let mut alpha = alpha;
+    let mut beta = beta;
+    if !is_root {
+        alpha = alpha.max(Evaluation::new_mate_in(ply));
+        beta = beta.min(-Evaluation::new_mate_in(ply + 1));
+        if alpha >= beta {
+            return alpha;
         }
     }
 
+    let (tt_move, tt_value) = search_data
+        .transposition_table
+        .get(position, alpha, beta, depth, ply);
+
+    if let Some(tt_value) = tt_value
+        && !is_root
+        && excluded_move.is_none()
+    {
+        return tt_value;
+    }
+
     let is_in_check = position.is_in_check();
 
     if depth == 0 && !is_in_check {
@@ -37,26 +102,127 @@ pub fn alpha_beta(
     let mut moves = MoveList::new();
     generate_moves(position, &mut moves);
 
+    if is_root && !search_data.search_moves.is_empty() {
+        moves.retain(|mv| search_data.search_moves.contains(mv));
+    }
+
     if moves.is_empty() {
         if is_in_check {
-            return Evaluation::new_mate_in(search_data.current_depth);
+            return Evaluation::new_mate_in(ply);
         } else {
             return Evaluation::DRAW;
         }
     }
 
+    if let Some(excluded_move) = excluded_move {
+        moves.retain(|mv| *mv != excluded_move);
+        // Without other moves the excluded move is singular.
+        if moves.is_empty() {
+            return alpha;
+        }
+    }
+
     if depth == 0 {
         return quiescence(alpha, beta, position, pv, search_data);
     }
 
-    let mut alpha = alpha;
+    // If passing the turn still fails high, a real move will almost certainly do so as well.
+    // Null moves are not tried in check, where passing is illegal, twice in a row, or when only
+    // pawns are left, where zugzwang makes passing better than any move.
+    let after_null_move = search_data.null_move;
+    if !is_root
+        && !after_null_move
+        && excluded_move.is_none()
+        && depth >= NULL_MOVE_MIN_DEPTH
+        && !is_in_check
+        && !beta.is_checkmate()
+        && position.has_non_pawn_material()
+        && relative_eval(position) >= beta
+    {
+        let reduction = NULL_MOVE_REDUCTION + depth / 6;
+
+        search_data.current_depth += 1;
+        search_data.null_move = true;
+        search_data.history.push(position.zobrist);
+        let null_move_history = mem::replace(
+            &mut search_data.null_move_history,
+            search_data.history.len(),
+        );
+        let undo_info = position.make_null_move();
+        let score = -alpha_beta(
+            -beta,
+            -Evaluation::new(beta.centipawns() - 1),
+            depth.saturating_sub(1 + reduction),
+            &mut Vec::new(),
+            position,
+            search_data,
+        );
+        position.unmake_null_move(undo_info);
+        search_data.null_move_history = null_move_history;
+        search_data.history.pop();
+        search_data.null_move = false;
+        search_data.current_depth -= 1;
+
+        if search_data.is_stopped() {
+            return alpha;
+        }
+
+        if score >= beta {
+            return beta;
+        }
+    }
+
+    // The move from the transposition table is extended if every other move fails low against a
+    // margin below its score, searched with reduced depth.
+    let mut singular_move = None;
+    if let Some(tt_move) = tt_move
+        && search_data.extensions.singular
+        && !is_root
+        && excluded_move.is_none()
+        && depth >= SINGULAR_MIN_DEPTH
+        && ply < MAX_EXTENSION_PLY
+        && let Some(entry) = search_data.transposition_table.get_entry(position.zobrist)
+        && entry.depth + SINGULAR_TT_DEPTH_MARGIN >= depth
+        && !matches!(entry.entry_type, TranspositionEntryType::UpperBound)
+        && !entry.score.is_checkmate()
+    {
+        let singular_beta =
+            Evaluation::new(entry.score.centipawns() - SINGULAR_MARGIN * depth as i16);
+
+        search_data.excluded_move = Some(tt_move);
+        let score = alpha_beta(
+            Evaluation::new(singular_beta.centipawns() - 1),
+            singular_beta,
+            (depth - 1) / 2,
+            &mut Vec::new(),
+            position,
+            search_data,
+        );
+        search_data.excluded_move = None;
+
+        if search_data.is_stopped() {
+            return alpha;
+        }
+
+        if score < singular_beta {
+            singular_move = Some(tt_move);
+        }
+    }
 
     let mut entry_type = TranspositionEntryType::UpperBound;
 
-    sort_moves(&mut moves, tt_move);
+    let color = position.color_to_move;
+    sort_moves(
+        &mut moves,
+        tt_move,
+        search_data.killer_moves.get(ply),
+        &search_data.history_table,
+        position,
+    );
 
+    let mut searched_quiets = MoveList::new();
     let mut best_move = *moves.first().unwrap();
-    for mv in moves {
+    for (move_index, mv) in moves.into_iter().enumerate() {
         search_data.nodes += 1;
 
         if search_data.nodes > search_data.max_nodes {
@@ -66,31 +232,126 @@ pub fn alpha_beta(
         search_data.current_depth += 1;
 
         let mut local_pv = Vec::new();
-        let mut next_position = *position;
-        next_position.make_move(mv);
-        let score = -alpha_beta(
-            -beta,
-            -alpha,
-            depth - 1,
-            &mut local_pv,
-            &next_position,
-            search_data,
-        );
+        search_data.null_move = false;
+        search_data.history.push(position.zobrist);
+        let undo_info = position.make_move(mv);
+        let gives_check = position.is_in_check();
+
+        let extension = if ply >= MAX_EXTENSION_PLY {
+            0
+        } else if singular_move == Some(mv) || (gives_check && search_data.extensions.check) {
+            1
+        } else {
+            0
+        };
+        let new_depth = depth - 1 + extension;
+
+        // The first move is expected to be the best and searched with the full window. All other
+        // moves only have to be proven worse than it with a null window, and quiet moves late in
+        // the list are searched with reduced depth. Only if a move unexpectedly beats alpha is it
+        // searched again at full depth and with the full window.
+        let score = if move_index == 0 {
+            -alpha_beta(
+                -beta,
+                -alpha,
+                new_depth,
+                &mut local_pv,
+                position,
+                search_data,
+            )
+        } else {
+            let null_window = -Evaluation::new(alpha.centipawns() + 1);
+            let reduction = if depth >= LMR_MIN_DEPTH
+                && move_index >= LMR_MIN_MOVE_INDEX
+                && !is_in_check
+                && is_quiet(&mv)
+                && !gives_check
+            {
+                late_move_reduction(depth, move_index).min(depth - 2)
+            } else {
+                0
+            };
+
+            let mut score = -alpha_beta(
+                null_window,
+                -alpha,
+                new_depth - reduction,
+                &mut local_pv,
+                position,
+                search_data,
+            );
+
+            if score > alpha && reduction > 0 {
+                local_pv.clear();
+                score = -alpha_beta(
+                    null_window,
+                    -alpha,
+                    new_depth,
+                    &mut local_pv,
+                    position,
+                    search_data,
+                );
+            }
+
+            if score > alpha && score < beta {
+                local_pv.clear();
+                score = -alpha_beta(
+                    -beta,
+                    -alpha,
+                    new_depth,
+                    &mut local_pv,
+                    position,
+                    search_data,
+                );
+            }
+
+            score
+        };
+
+        position.unmake_move(mv, undo_info);
+        search_data.history.pop();
         search_data.current_depth -= 1;
 
+        if search_data.is_stopped() {
+            return alpha;
+        }
+
         if score >= beta {
-            let mut tt_table = search_data.transposition_table.lock().unwrap();
-            tt_table.insert(TranspositionEntry {
-                zobrist: position.zobrist,
-                score: beta,
-                depth,
-                entry_type: TranspositionEntryType::LowerBound,
-                chess_move: mv,
-            });
+            if is_quiet(&mv) {
+                search_data.killer_moves.insert(ply, mv);
+                search_data.history_table.update(color, mv, depth);
+                for quiet in searched_quiets {
+                    search_data.history_table.penalize(color, quiet, depth);
+                }
+            }
+
+            // The move that failed high is kept, as at the root it is reported as a lower bound and
+            // a mate is a cutoff once mate distance pruning lowered beta to it.
+            pv.clear();
+            pv.push(mv);
+            pv.append(&mut local_pv);
+
+            // The score without the excluded move is not the score of the position.
+            if excluded_move.is_none() {
+                search_data.transposition_table.insert(
+                    TranspositionEntry {
+                        zobrist: position.zobrist,
+                        score: beta,
+                        depth,
+                        entry_type: TranspositionEntryType::LowerBound,
+                        chess_move: mv,
+                    },
+                    ply,
+                );
+            }
 
             return beta;
         }
 
+        if is_quiet(&mv) {
+            searched_quiets.push(mv);
+        }
+
         if score > alpha {
             entry_type = TranspositionEntryType::Exact;
 
@@ -102,14 +363,39 @@ pub fn alpha_beta(
         }
     }
 
-    let mut tt_table = search_data.transposition_table.lock().unwrap();
-    tt_table.insert(TranspositionEntry {
-        zobrist: position.zobrist,
-        score: alpha,
-        depth,
-        entry_type,
-        chess_move: best_move,
-    });
+    if excluded_move.is_none() {
+        search_data.transposition_table.insert(
+            TranspositionEntry {
+                zobrist: position.zobrist,
+                score: alpha,
+                depth,
+                entry_type,
+                chess_move: best_move,
+            },
+            ply,
+        );
+    }
 
     alpha
 }
+
+/// Evaluates the position from the view of the color to move.
+fn relative_eval(position: &Position) -> Evaluation {
+    match position.color_to_move {
+        Color::White => eval(position),
+        Color::Black => -eval(position),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_move_reductions() {
+        assert_eq!(late_move_reduction(1, 1), 0);
+        assert!(late_move_reduction(3, 3) >= 1);
+        assert!(late_move_reduction(20, 40) > late_move_reduction(3, 3));
+        assert_eq!(late_move_reduction(u8::MAX, 200), LMR_TABLE[63][63]);
+    }
+}
@@ -1,7 +1,10 @@
-use std::i16;
-
 use cherris_core::{Color, Move, Square};
 
+/// The largest absolute value a history score can reach.
+pub const MAX_HISTORY: i16 = 16384;
+
+/// Scores quiet moves by how often they caused a beta cutoff, indexed by color, from and to square.
+#[derive(Clone)]
 pub struct HistoryTable([[[i16; Square::COUNT]; Square::COUNT]; Color::COUNT]);
 
 impl HistoryTable {
@@ -17,15 +20,39 @@ impl HistoryTable {
         }
     }
 
+    /// Rewards a quiet move that caused a beta cutoff at `depth`.
     pub fn update(&mut self, color: Color, chess_move: Move, depth: u8) {
+        self.add_bonus(color, chess_move, bonus(depth));
+    }
+
+    /// Punishes a quiet move that was searched before the move that caused a beta cutoff.
+    pub fn penalize(&mut self, color: Color, chess_move: Move, depth: u8) {
+        self.add_bonus(color, chess_move, -bonus(depth));
+    }
+
+    /// Halves all scores, so moves that were good in earlier searches or iterations lose weight.
+    pub fn age(&mut self) {
+        for score in self.0.iter_mut().flatten().flatten() {
+            *score /= 2;
+        }
+    }
+
+    fn add_bonus(&mut self, color: Color, chess_move: Move, bonus: i16) {
         if let Move::Standard {
             from,
             to,
             capture: None,
+            promotion: None,
             ..
         } = chess_move
         {
-            self.0[color][from][to] += (depth * depth) as i16
+            // The bonus shrinks the closer the score gets to `MAX_HISTORY`, so scores can't
+            // overflow and moves that stopped being good lose their score quickly.
+            let score = &mut self.0[color][from][to];
+            let bonus = i32::from(bonus);
+            let scaled = bonus - i32::from(*score) * bonus.abs() / i32::from(MAX_HISTORY);
+            *score = (i32::from(*score) + scaled)
+                .clamp(-i32::from(MAX_HISTORY), i32::from(MAX_HISTORY)) as i16;
         }
     }
 }
@@ -35,3 +62,78 @@ impl Default for HistoryTable {
         Self::new()
     }
 }
+
+fn bonus(depth: u8) -> i16 {
+    (i32::from(depth) * i32::from(depth)).min(i32::from(MAX_HISTORY / 4)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use cherris_core::Role;
+
+    use super::*;
+
+    fn quiet_move() -> Move {
+        Move::Standard {
+            from: Square::G1,
+            to: Square::F3,
+            role: Role::Knight,
+            capture: None,
+            promotion: None,
+            en_passant_square: None,
+        }
+    }
+
+    #[test]
+    fn update_and_penalize() {
+        let mut history_table = HistoryTable::new();
+
+        history_table.update(Color::White, quiet_move(), 4);
+
+        assert_eq!(history_table.get(Color::White, quiet_move()), 16);
+        assert_eq!(history_table.get(Color::Black, quiet_move()), 0);
+
+        history_table.penalize(Color::White, quiet_move(), 4);
+
+        assert!(history_table.get(Color::White, quiet_move()) < 16);
+    }
+
+    #[test]
+    fn captures_not_updated() {
+        let mut history_table = HistoryTable::new();
+        let capture = Move::Standard {
+            from: Square::G1,
+            to: Square::F3,
+            role: Role::Knight,
+            capture: Some(Role::Pawn),
+            promotion: None,
+            en_passant_square: None,
+        };
+
+        history_table.update(Color::White, capture, 10);
+
+        assert_eq!(history_table.get(Color::White, capture), 0);
+    }
+
+    #[test]
+    fn no_overflow() {
+        let mut history_table = HistoryTable::new();
+
+        for _ in 0..10000 {
+            history_table.update(Color::White, quiet_move(), u8::MAX);
+        }
+
+        let score = history_table.get(Color::White, quiet_move());
+        assert!(score > 0 && score <= MAX_HISTORY);
+    }
+
+    #[test]
+    fn age() {
+        let mut history_table = HistoryTable::new();
+        history_table.update(Color::White, quiet_move(), 10);
+
+        history_table.age();
+
+        assert_eq!(history_table.get(Color::White, quiet_move()), 50);
+    }
+}
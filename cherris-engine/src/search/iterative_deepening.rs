@@ -1,81 +1,650 @@
 use std::{
-    sync::{Arc, Mutex},
+    mem,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
     time::Instant,
 };
 
 use cherris_core::{
-    Move, Position,
-    uci::{UCIGuiCommand, UCISearchInfo},
+    Move, Position, Zobrist,
+    uci::{UCIGuiCommand, UCIScoreBound, UCISearchInfo},
 };
 
 use crate::{
-    SearchData, alpha_beta::alpha_beta, evaluation::Evaluation, time_managment::TimeManagment,
-    transposition_table::TranspositionTable,
+    SearchData, SearchLimits, alpha_beta::alpha_beta, evaluation::Evaluation,
+    history::HistoryTable, killer_moves::KillerMoves, transposition_table::TranspositionTable,
 };
 
+/// Searches the position with increasing depth until one of the `search_limits` is reached and
+/// returns the principal variation of the last completed iteration. The returned pv always
+/// contains at least one move.
+/// The nodes searched are added to `total_nodes`, which is shared by all threads of a search. Only
+/// the main thread with `thread_id` 0 reports its progress by passing infos to `output`, while
+/// helper threads with odd ids start one ply deeper so the threads don't all search the same depth.
+/// With `multi_pv` set to N every iteration searches the root N times, each time without the best
+/// moves of the lines found before, and reports every line separately.
+/// From `ASPIRATION_MIN_DEPTH` on, the best line is searched with an aspiration window around the
+/// score of the previous iteration. Searches that fail outside of it are reported as bounds.
+#[allow(clippy::too_many_arguments)]
 pub fn iterative_deepening(
     position: Position,
-    max_depth: u8,
-    max_nodes: u64,
-    time_managment: TimeManagment,
-    transposition_table: Arc<Mutex<TranspositionTable>>,
-) -> Move {
-    let mut depth = 1;
-    let mut pv = Vec::with_capacity(max_depth.into());
-
-    while depth <= max_depth {
+    history: &[Zobrist],
+    search_limits: &SearchLimits,
+    transposition_table: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    total_nodes: Arc<AtomicU64>,
+    thread_id: usize,
+    output: impl Fn(UCIGuiCommand),
+) -> Vec<Move> {
+    let is_main_thread = thread_id == 0;
+    let mut depth = 1 + (thread_id % 2) as u8;
+    let mut pv = Vec::with_capacity(search_limits.max_depth.into());
+    let search_timer = Instant::now();
+
+    let root_moves: Vec<Move> = if search_limits.search_moves.is_empty() {
+        position.legal_moves().to_vec()
+    } else {
+        search_limits.search_moves.clone()
+    };
+    let multi_pv = search_limits.multi_pv.clamp(1, root_moves.len().max(1));
+
+    // The move ordering tables are kept between iterations, as the best moves of one iteration
+    // are usually good in the next one as well.
+    let mut history_table = HistoryTable::new();
+    let mut killer_moves = KillerMoves::new();
+
+    let mut previous_eval = None;
+    let reporter = InfoReporter {
+        search_timer,
+        total_nodes: total_nodes.clone(),
+        output,
+    };
+
+    while depth <= search_limits.max_depth {
         let timer = Instant::now();
-        let mut search_data = SearchData {
-            nodes: 0,
-            max_nodes,
-            pv: pv.clone(),
-            transposition_table: transposition_table.clone(),
-            current_depth: 0,
-            selective_depth: 0,
-        };
+        let mut lines: Vec<(Evaluation, Vec<Move>, u8)> = Vec::with_capacity(multi_pv);
+        let mut stopped = false;
+        let mut node_limit_reached = false;
 
-        pv.clear();
+        while lines.len() < multi_pv {
+            let search_moves = if lines.is_empty() {
+                search_limits.search_moves.clone()
+            } else {
+                root_moves
+                    .iter()
+                    .filter(|mv| !lines.iter().any(|(_, line, _)| line[0] == **mv))
+                    .copied()
+                    .collect()
+            };
 
-        let eval = alpha_beta(
-            Evaluation::MIN,
-            Evaluation::MAX,
-            depth,
-            &mut pv,
-            &position,
-            &mut search_data,
-        );
+            let mut search_data = SearchData {
+                nodes: 0,
+                max_nodes: search_limits.max_nodes,
+                pv: pv.clone(),
+                transposition_table: transposition_table.clone(),
+                current_depth: 0,
+                selective_depth: 0,
+                search_moves,
+                time_managment: search_limits.time_managment.clone(),
+                stop: stop.clone(),
+                stopped: false,
+                total_nodes: total_nodes.clone(),
+                reported_nodes: 0,
+                history: history.to_vec(),
+                null_move: false,
+                null_move_history: 0,
+                history_table: mem::take(&mut history_table),
+                killer_moves: mem::take(&mut killer_moves),
+                extensions: search_limits.extensions,
+                excluded_move: None,
+            };
 
-        let elapsed = timer.elapsed().as_millis().max(1);
-        let nps = ((search_data.nodes as u128) / elapsed) as u64 * 1000;
+            let mut line = Vec::with_capacity(search_limits.max_depth.into());
+            let mut root = position;
 
-        let search_info = UCISearchInfo {
-            depth,
-            seldepth: search_data.selective_depth,
-            time: elapsed,
-            score: eval.into(),
-            nodes: search_data.nodes,
-            pv: pv.iter().map(|mv| mv.to_string()).collect(),
-            nps,
+            // The best line is searched with a narrow window around the score of the previous
+            // iteration, which is widened on the side the search fails on until the score fits.
+            let mut window = match previous_eval {
+                Some(previous_eval) if lines.is_empty() && depth >= ASPIRATION_MIN_DEPTH => {
+                    AspirationWindow::new(previous_eval)
+                }
+                _ => AspirationWindow::full(),
+            };
+
+            let eval = loop {
+                line.clear();
+                let eval = alpha_beta(
+                    window.alpha,
+                    window.beta,
+                    depth,
+                    &mut line,
+                    &mut root,
+                    &mut search_data,
+                );
+
+                if search_data.is_stopped() || search_data.nodes > search_data.max_nodes {
+                    break eval;
+                }
+
+                let bound = if eval <= window.alpha && window.alpha > Evaluation::MIN {
+                    window.widen_alpha();
+                    UCIScoreBound::UpperBound
+                } else if eval >= window.beta && window.beta < Evaluation::MAX {
+                    window.widen_beta();
+                    UCIScoreBound::LowerBound
+                } else {
+                    break eval;
+                };
+
+                if is_main_thread {
+                    // A fail low leaves no line, so the best line so far is reported instead.
+                    let reported_line = if line.is_empty() { &pv } else { &line };
+                    reporter.report(
+                        &position,
+                        depth,
+                        1,
+                        (eval, reported_line, search_data.selective_depth),
+                        bound,
+                    );
+                }
+            };
+            search_data.report_nodes();
+            history_table = mem::take(&mut search_data.history_table);
+            killer_moves = mem::take(&mut search_data.killer_moves);
+
+            if search_data.is_stopped() {
+                stopped = true;
+                if pv.is_empty() && lines.is_empty() {
+                    pv = line;
+                }
+                break;
+            }
+
+            node_limit_reached = search_data.nodes > search_data.max_nodes;
+            if line.is_empty() {
+                break;
+            }
+
+            lines.push((eval, line, search_data.selective_depth));
+
+            if node_limit_reached {
+                break;
+            }
+        }
+
+        // The result of an interrupted iteration can't be trusted, so the pv of the last completed
+        // iteration is kept unless there is none yet.
+        if stopped {
+            if pv.is_empty()
+                && let Some((_, line, _)) = lines.first()
+            {
+                pv = line.clone();
+            }
+            break;
+        }
+
+        history_table.age();
+
+        let Some((eval, best_line, _)) = lines.first() else {
+            break;
         };
-        let info_command = UCIGuiCommand::Info(search_info);
-        if !cfg!(test) {
-            print!("{}", info_command);
+        let eval = *eval;
+        pv = best_line.clone();
+        previous_eval = Some(eval);
+
+        if is_main_thread {
+            for (index, (eval, line, seldepth)) in lines.iter().enumerate() {
+                reporter.report(
+                    &position,
+                    depth,
+                    index as u16 + 1,
+                    (*eval, line, *seldepth),
+                    UCIScoreBound::Exact,
+                );
+            }
         }
 
-        if eval.is_checkmate() {
+        // Extensions can find a long mate before a shorter one, which is only certain to be
+        // found once the depth reaches the length of the mate.
+        if eval.is_checkmate() && i16::from(depth) >= eval.mate_in() {
             break;
         }
 
-        if search_data.nodes > search_data.max_nodes {
+        if node_limit_reached {
             break;
         }
 
-        if !time_managment.has_time_for_next_iteration(timer.elapsed().as_millis()) {
+        if !search_limits
+            .time_managment
+            .has_time_for_next_iteration(timer.elapsed().as_millis())
+        {
             break;
         }
 
         depth += 1;
     }
 
-    pv[0]
+    if pv.is_empty() {
+        pv.push(position.legal_moves()[0]);
+    }
+
+    pv
+}
+
+/// The window around the previous score that a search is started with, in centipawns.
+const ASPIRATION_DELTA: i16 = 25;
+/// The first depth that is searched with an aspiration window. The scores of shallower searches
+/// change too much between iterations.
+const ASPIRATION_MIN_DEPTH: u8 = 4;
+/// The distance from the previous score after which a bound is dropped completely.
+const ASPIRATION_MAX_DELTA: i16 = 1000;
+
+/// The bounds the root is searched with.
+struct AspirationWindow {
+    center: Evaluation,
+    alpha: Evaluation,
+    beta: Evaluation,
+    alpha_delta: i16,
+    beta_delta: i16,
+}
+
+impl AspirationWindow {
+    fn new(center: Evaluation) -> AspirationWindow {
+        if center.is_checkmate() {
+            return AspirationWindow::full();
+        }
+
+        AspirationWindow {
+            center,
+            alpha: offset(center, -ASPIRATION_DELTA),
+            beta: offset(center, ASPIRATION_DELTA),
+            alpha_delta: ASPIRATION_DELTA,
+            beta_delta: ASPIRATION_DELTA,
+        }
+    }
+
+    fn full() -> AspirationWindow {
+        AspirationWindow {
+            center: Evaluation::DRAW,
+            alpha: Evaluation::MIN,
+            beta: Evaluation::MAX,
+            alpha_delta: ASPIRATION_MAX_DELTA,
+            beta_delta: ASPIRATION_MAX_DELTA,
+        }
+    }
+
+    /// Lowers alpha after the search failed low.
+    fn widen_alpha(&mut self) {
+        self.alpha_delta = self.alpha_delta.saturating_mul(2);
+        self.alpha = if self.alpha_delta >= ASPIRATION_MAX_DELTA {
+            Evaluation::MIN
+        } else {
+            offset(self.center, -self.alpha_delta)
+        };
+    }
+
+    /// Raises beta after the search failed high.
+    fn widen_beta(&mut self) {
+        self.beta_delta = self.beta_delta.saturating_mul(2);
+        self.beta = if self.beta_delta >= ASPIRATION_MAX_DELTA {
+            Evaluation::MAX
+        } else {
+            offset(self.center, self.beta_delta)
+        };
+    }
+}
+
+fn offset(eval: Evaluation, delta: i16) -> Evaluation {
+    let centipawns = i32::from(eval.centipawns()) + i32::from(delta);
+    let min = i32::from(Evaluation::MIN.centipawns());
+    let max = i32::from(Evaluation::MAX.centipawns());
+
+    Evaluation::new(centipawns.clamp(min, max) as i16)
+}
+
+/// Passes the progress of the search to the output of the main thread.
+struct InfoReporter<F: Fn(UCIGuiCommand)> {
+    search_timer: Instant,
+    total_nodes: Arc<AtomicU64>,
+    output: F,
+}
+
+impl<F: Fn(UCIGuiCommand)> InfoReporter<F> {
+    fn report(
+        &self,
+        position: &Position,
+        depth: u8,
+        multipv: u16,
+        (eval, line, seldepth): (Evaluation, &[Move], u8),
+        bound: UCIScoreBound,
+    ) {
+        let elapsed = self.search_timer.elapsed().as_millis().max(1);
+        let nodes = self.total_nodes.load(Ordering::Relaxed);
+        let nps = (nodes as u128 * 1000 / elapsed) as u64;
+
+        let search_info = UCISearchInfo {
+            depth: Some(depth),
+            seldepth: Some(seldepth),
+            multipv: Some(multipv),
+            time: Some(elapsed),
+            score: Some(eval.into()),
+            bound,
+            nodes: Some(nodes),
+            pv: to_uci_line(position, line),
+            nps: Some(nps),
+            ..Default::default()
+        };
+        (self.output)(UCIGuiCommand::Info(search_info));
+    }
+}
+
+/// Converts a line starting at `position` to the move notation used by UCI.
+fn to_uci_line(position: &Position, line: &[Move]) -> Vec<String> {
+    let mut color = position.color_to_move;
+
+    line.iter()
+        .map(|mv| {
+            let uci_move = mv.to_uci(color);
+            color = !color;
+            uci_move
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, str::FromStr, thread, time::Duration};
+
+    use cherris_core::{Role, Square, generate_lookup_tables, uci::UCIScore};
+
+    use crate::{Extensions, MAX_DEPTH, time_managment::TimeManagment};
+
+    use super::*;
+
+    fn search_limits(max_depth: u8, multi_pv: usize) -> SearchLimits {
+        SearchLimits {
+            max_depth,
+            max_nodes: u64::MAX,
+            search_moves: vec![],
+            multi_pv,
+            time_managment: TimeManagment::infinite(),
+            extensions: Extensions::default(),
+        }
+    }
+
+    /// Runs the main thread of a search and returns its pv together with the infos it reported.
+    fn run_search(
+        position: Position,
+        search_limits: &SearchLimits,
+        stop: Arc<AtomicBool>,
+    ) -> (Vec<Move>, Vec<UCISearchInfo>) {
+        let reported = RefCell::new(Vec::new());
+
+        let pv = iterative_deepening(
+            position,
+            &[],
+            search_limits,
+            Arc::new(TranspositionTable::new(2_u64.pow(16))),
+            stop,
+            Arc::new(AtomicU64::new(0)),
+            0,
+            |command| {
+                if let UCIGuiCommand::Info(info) = command {
+                    reported.borrow_mut().push(info);
+                }
+            },
+        );
+
+        (pv, reported.into_inner())
+    }
+
+    /// Searches `position` to `max_depth` without a time limit.
+    fn search(
+        position: Position,
+        max_depth: u8,
+        multi_pv: usize,
+    ) -> (Vec<Move>, Vec<UCISearchInfo>) {
+        run_search(
+            position,
+            &search_limits(max_depth, multi_pv),
+            Arc::new(AtomicBool::new(false)),
+        )
+    }
+
+    /// Orders scores from the best to the worst for the side to move.
+    fn score_order(score: UCIScore) -> i32 {
+        match score {
+            UCIScore::Mate(moves) if moves > 0 => 100_000 - i32::from(moves),
+            UCIScore::Mate(moves) => -100_000 - i32::from(moves),
+            UCIScore::Centipawns(centipawns) => i32::from(centipawns),
+        }
+    }
+
+    #[test]
+    fn stop_returns_legal_move() {
+        generate_lookup_tables();
+
+        let position = Position::default();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let search_stop = stop.clone();
+        let search = thread::spawn(move || {
+            run_search(position, &search_limits(MAX_DEPTH, 1), search_stop).0
+        });
+
+        thread::sleep(Duration::from_millis(100));
+        stop.store(true, Ordering::Relaxed);
+
+        let pv = search.join().unwrap();
+        assert!(position.legal_moves().contains(&pv[0]));
+    }
+
+    #[test]
+    fn movetime_stops_search() {
+        generate_lookup_tables();
+
+        let search_limits = SearchLimits {
+            time_managment: TimeManagment::fixed(100),
+            ..search_limits(MAX_DEPTH, 1)
+        };
+        let timer = Instant::now();
+
+        run_search(
+            Position::default(),
+            &search_limits,
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        assert!(timer.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn search_moves_restrict_root() {
+        generate_lookup_tables();
+
+        let search_move = Move::Standard {
+            from: Square::A2,
+            to: Square::A3,
+            role: Role::Pawn,
+            capture: None,
+            promotion: None,
+            en_passant_square: None,
+        };
+        let search_limits = SearchLimits {
+            search_moves: vec![search_move],
+            ..search_limits(3, 1)
+        };
+
+        let (pv, _) = run_search(
+            Position::default(),
+            &search_limits,
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        assert_eq!(pv[0], search_move);
+    }
+
+    #[test]
+    fn mate_in_one() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+
+        let (pv, _) = search(position, 1, 1);
+
+        assert_eq!(
+            pv[0],
+            Move::Standard {
+                from: Square::A1,
+                to: Square::A8,
+                role: Role::Rook,
+                capture: None,
+                promotion: None,
+                en_passant_square: None,
+            }
+        );
+    }
+
+    #[test]
+    fn multi_pv_reports_distinct_lines() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+
+        let (pv, reported) = search(position, 3, 3);
+
+        // The mate has to stay the main line even if other lines are searched after it.
+        assert_eq!(
+            pv[0],
+            Move::Standard {
+                from: Square::A1,
+                to: Square::A8,
+                role: Role::Rook,
+                capture: None,
+                promotion: None,
+                en_passant_square: None,
+            }
+        );
+
+        // The lines of the last iteration, as the search ends early once the mate is found.
+        let reported: Vec<UCISearchInfo> = reported
+            .into_iter()
+            .filter(|info| info.bound == UCIScoreBound::Exact)
+            .collect();
+        let last_depth = reported.last().and_then(|info| info.depth);
+        let lines: Vec<&UCISearchInfo> = reported
+            .iter()
+            .filter(|info| info.depth == last_depth)
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line.multipv, Some(index as u16 + 1));
+        }
+        assert_eq!(lines[0].pv[0], "a1a8");
+        assert_eq!(lines[0].score, Some(UCIScore::Mate(1)));
+        assert!(lines[1].pv[0] != lines[0].pv[0]);
+        assert!(lines[2].pv[0] != lines[0].pv[0] && lines[2].pv[0] != lines[1].pv[0]);
+
+        let scores: Vec<i32> = lines
+            .iter()
+            .map(|line| score_order(line.score.unwrap()))
+            .collect();
+        assert!(scores.is_sorted_by(|a, b| a >= b), "{:?}", scores);
+    }
+
+    #[test]
+    fn aspiration_window_widens() {
+        let mut window = AspirationWindow::new(Evaluation::new(50));
+        assert_eq!(window.alpha, Evaluation::new(25));
+        assert_eq!(window.beta, Evaluation::new(75));
+
+        window.widen_beta();
+        assert_eq!(window.alpha, Evaluation::new(25));
+        assert_eq!(window.beta, Evaluation::new(100));
+
+        for _ in 0..6 {
+            window.widen_alpha();
+        }
+        assert_eq!(window.alpha, Evaluation::MIN);
+
+        let window = AspirationWindow::new(Evaluation::new_mate_in(3));
+        assert_eq!(window.alpha, Evaluation::MIN);
+        assert_eq!(window.beta, Evaluation::MAX);
+    }
+
+    #[test]
+    fn aspiration_window_fail_high_finds_mate() {
+        generate_lookup_tables();
+
+        // The mate in three is only seen at depth 5, where the window around the score of
+        // depth 4 fails high and has to be widened.
+        let position =
+            Position::from_str("r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1")
+                .unwrap();
+        let (pv, _) = search(position, 5, 1);
+
+        assert_eq!(
+            pv[0],
+            Move::Standard {
+                from: Square::F8,
+                to: Square::C5,
+                role: Role::Bishop,
+                capture: None,
+                promotion: None,
+                en_passant_square: None,
+            }
+        );
+    }
+
+    fn mate_in_three_search(max_depth: u8, extensions: Extensions) -> Vec<Move> {
+        generate_lookup_tables();
+
+        let position =
+            Position::from_str("r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1")
+                .unwrap();
+        let search_limits = SearchLimits {
+            extensions,
+            ..search_limits(max_depth, 1)
+        };
+
+        run_search(position, &search_limits, Arc::new(AtomicBool::new(false))).0
+    }
+
+    #[test]
+    fn check_extension_finds_deeper_mate() {
+        // Every move of the mate gives check, so with check extensions a search of depth 3
+        // already sees the mate after 5 plies.
+        let pv = mate_in_three_search(3, Extensions::default());
+
+        assert_eq!(pv.len(), 5);
+        assert_eq!(pv[0].to_string(), "f8c5");
+    }
+
+    #[test]
+    fn shortest_mate() {
+        let extensions = Extensions {
+            check: false,
+            singular: false,
+        };
+
+        let pv = mate_in_three_search(8, extensions);
+
+        assert_eq!(pv.len(), 5);
+        assert_eq!(pv[0].to_string(), "f8c5");
+    }
+
+    #[test]
+    fn uci_line_castling() {
+        generate_lookup_tables();
+
+        let position = Position::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        assert_eq!(
+            to_uci_line(&position, &[Move::CastleShort, Move::CastleLong]),
+            vec!["e1g1", "e8c8"]
+        );
+    }
 }
@@ -0,0 +1,68 @@
+use cherris_core::Move;
+
+/// The number of plies killer moves are stored for.
+const MAX_PLY: usize = 128;
+
+/// Stores two quiet moves per ply that recently caused a beta cutoff in a sibling node. They are
+/// likely to cause a cutoff again and are tried right after the good captures.
+#[derive(Clone)]
+pub struct KillerMoves([[Option<Move>; 2]; MAX_PLY]);
+
+impl KillerMoves {
+    pub fn new() -> KillerMoves {
+        KillerMoves([[None; 2]; MAX_PLY])
+    }
+
+    /// Returns the killer moves for `ply`, the most recent one first.
+    pub fn get(&self, ply: u8) -> [Option<Move>; 2] {
+        self.0.get(usize::from(ply)).copied().unwrap_or_default()
+    }
+
+    /// Stores a move that caused a beta cutoff at `ply`, replacing the older of the two killers.
+    pub fn insert(&mut self, ply: u8, chess_move: Move) {
+        if let Some(killers) = self.0.get_mut(usize::from(ply))
+            && killers[0] != Some(chess_move)
+        {
+            killers[1] = killers[0];
+            killers[0] = Some(chess_move);
+        }
+    }
+}
+
+impl Default for KillerMoves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_keeps_two_moves() {
+        let mut killer_moves = KillerMoves::new();
+
+        killer_moves.insert(3, Move::CastleShort);
+        killer_moves.insert(3, Move::CastleShort);
+
+        assert_eq!(killer_moves.get(3), [Some(Move::CastleShort), None]);
+
+        killer_moves.insert(3, Move::CastleLong);
+
+        assert_eq!(
+            killer_moves.get(3),
+            [Some(Move::CastleLong), Some(Move::CastleShort)]
+        );
+        assert_eq!(killer_moves.get(2), [None, None]);
+    }
+
+    #[test]
+    fn ply_out_of_range() {
+        let mut killer_moves = KillerMoves::new();
+
+        killer_moves.insert(u8::MAX, Move::CastleShort);
+
+        assert_eq!(killer_moves.get(u8::MAX), [None, None]);
+    }
+}
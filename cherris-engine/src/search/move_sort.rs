@@ -1,25 +1,152 @@
-use cherris_core::{Move, MoveList};
+use std::cmp::Reverse;
 
-use crate::ROLE_VALUE;
+use cherris_core::{Move, MoveList, Position};
 
-pub fn sort_moves(moves: &mut MoveList, tt_move: Option<Move>) {
-    moves.sort_by_key(|mv| score_move(mv, tt_move))
+use crate::{ROLE_VALUE, history::HistoryTable};
+
+const TT_MOVE_SCORE: i32 = 3_000_000;
+const GOOD_CAPTURE_SCORE: i32 = 2_000_000;
+const KILLER_SCORE: i32 = 1_000_000;
+const BAD_CAPTURE_SCORE: i32 = -1_000_000;
+
+/// Sorts the moves in the order they should be searched: the move from the transposition table,
+/// captures that don't lose material according to the static exchange evaluation, killer moves,
+/// the other quiet moves by their history score and finally captures that lose material.
+pub fn sort_moves(
+    moves: &mut MoveList,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    history_table: &HistoryTable,
+    position: &Position,
+) {
+    moves.sort_by_key(|mv| Reverse(score_move(mv, tt_move, killers, history_table, position)))
+}
+
+/// Checks if a move neither captures nor promotes.
+pub fn is_quiet(chess_move: &Move) -> bool {
+    match chess_move {
+        Move::Standard {
+            capture, promotion, ..
+        } => capture.is_none() && promotion.is_none(),
+        Move::EnPassant { .. } => false,
+        Move::CastleShort | Move::CastleLong => true,
+    }
 }
 
-fn score_move(mv: &Move, tt_move: Option<Move>) -> i16 {
-    if let Some(tt_move) = tt_move {
-        if mv == &tt_move {
-            return -10000;
+fn score_move(
+    mv: &Move,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    history_table: &HistoryTable,
+    position: &Position,
+) -> i32 {
+    if tt_move == Some(*mv) {
+        return TT_MOVE_SCORE;
+    }
+
+    match *mv {
+        Move::Standard {
+            role,
+            capture: Some(capture),
+            ..
+        } => {
+            let victim = i32::from(ROLE_VALUE[capture]);
+            let attacker = i32::from(ROLE_VALUE[role]);
+            let mvv_lva = victim * 16 - attacker;
+
+            // Capturing a piece that is worth at least as much as the attacker can't lose
+            // material, so the exchange only has to be evaluated for the other captures.
+            if victim >= attacker || position.see_ge(*mv, 0) {
+                GOOD_CAPTURE_SCORE + mvv_lva
+            } else {
+                BAD_CAPTURE_SCORE + mvv_lva
+            }
         }
+        Move::Standard {
+            promotion: Some(promotion),
+            ..
+        } => GOOD_CAPTURE_SCORE + i32::from(ROLE_VALUE[promotion]),
+        Move::EnPassant { .. } => GOOD_CAPTURE_SCORE,
+        _ if killers[0] == Some(*mv) => KILLER_SCORE + 1,
+        _ if killers[1] == Some(*mv) => KILLER_SCORE,
+        _ => i32::from(history_table.get(position.color_to_move, *mv)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cherris_core::{Color, Role, Square, generate_lookup_tables};
+
+    use super::*;
+
+    fn standard(from: Square, to: Square, role: Role, capture: Option<Role>) -> Move {
+        Move::Standard {
+            from,
+            to,
+            role,
+            capture,
+            promotion: None,
+            en_passant_square: None,
+        }
+    }
+
+    #[test]
+    fn sort_order() {
+        generate_lookup_tables();
+
+        let tt_move = standard(Square::A2, Square::A3, Role::Pawn, None);
+        let good_capture = standard(Square::B2, Square::C3, Role::Pawn, Some(Role::Knight));
+        let bad_capture = standard(Square::D1, Square::D7, Role::Queen, Some(Role::Pawn));
+        let killer = standard(Square::G1, Square::F3, Role::Knight, None);
+        let history_move = standard(Square::B1, Square::C3, Role::Knight, None);
+        let quiet = standard(Square::H2, Square::H3, Role::Pawn, None);
+
+        let mut history_table = HistoryTable::new();
+        history_table.update(Color::White, history_move, 5);
+
+        let mut moves = MoveList::new();
+        moves.push(quiet);
+        moves.push(bad_capture);
+        moves.push(history_move);
+        moves.push(killer);
+        moves.push(good_capture);
+        moves.push(tt_move);
+
+        sort_moves(
+            &mut moves,
+            Some(tt_move),
+            [Some(killer), None],
+            &history_table,
+            &Position::default(),
+        );
+
+        assert_eq!(
+            moves.as_slice(),
+            [
+                tt_move,
+                good_capture,
+                killer,
+                history_move,
+                quiet,
+                bad_capture
+            ]
+        );
     }
 
-    match mv {
-        Move::Standard { role, capture, .. } => match capture {
-            Some(capture) => ROLE_VALUE[role] - ROLE_VALUE[capture],
-            None => 0,
-        },
-        Move::EnPassant { .. } => 0,
-        Move::CastleShort => 1000,
-        Move::CastleLong => 1000,
+    #[test]
+    fn quiet_moves() {
+        assert!(is_quiet(&Move::CastleShort));
+        assert!(is_quiet(&standard(
+            Square::G1,
+            Square::F3,
+            Role::Knight,
+            None
+        )));
+        assert!(!is_quiet(&standard(
+            Square::G1,
+            Square::F3,
+            Role::Knight,
+            Some(Role::Pawn)
+        )));
     }
 }
@@ -5,7 +5,7 @@ use crate::{SearchData, eval, evaluation::Evaluation, move_sort::sort_moves};
 pub fn quiescence(
     alpha: Evaluation,
     beta: Evaluation,
-    position: &Position,
+    position: &mut Position,
     pv: &mut Vec<Move>,
     search_data: &mut SearchData,
 ) -> Evaluation {
@@ -21,7 +21,7 @@ pub fn quiescence(
         Color::Black => -eval(position),
     };
 
-    if search_data.nodes > search_data.max_nodes {
+    if search_data.nodes > search_data.max_nodes || search_data.is_stopped() {
         return stand_pat;
     }
 
@@ -34,24 +34,37 @@ pub fn quiescence(
     }
 
     let mut moves = MoveList::new();
-    if position.is_in_check() {
+    let is_in_check = position.is_in_check();
+    if is_in_check {
         generate_moves(position, &mut moves);
     } else {
         generate_loud_moves(position, &mut moves);
+        // Captures that lose material can't raise alpha above the stand pat.
+        moves.retain(|mv| position.see_ge(*mv, 0));
     }
 
-    sort_moves(&mut moves, None);
+    sort_moves(
+        &mut moves,
+        None,
+        [None; 2],
+        &search_data.history_table,
+        position,
+    );
 
     for mv in moves {
         let mut local_pv = Vec::new();
         search_data.current_depth += 1;
 
-        let mut next_position = *position;
-        next_position.make_move(mv);
-        let score = -quiescence(-beta, -alpha, &next_position, &mut local_pv, search_data);
+        let undo_info = position.make_move(mv);
+        let score = -quiescence(-beta, -alpha, position, &mut local_pv, search_data);
+        position.unmake_move(mv, undo_info);
 
         search_data.current_depth -= 1;
 
+        if search_data.is_stopped() {
+            return alpha;
+        }
+
         if score >= beta {
             return beta;
         }
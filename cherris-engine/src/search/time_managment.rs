@@ -1,11 +1,16 @@
-use std::time::Instant;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct TimeManagment {
     time_left: u128,
     increment: u128,
     start_time: Instant,
     moves_to_go: Option<u64>,
+    move_time: Option<u128>,
+    ponder_hit: Option<Arc<PonderHit>>,
 }
 
 impl TimeManagment {
@@ -15,15 +20,127 @@ impl TimeManagment {
             increment,
             start_time: Instant::now(),
             moves_to_go,
+            move_time: None,
+            ponder_hit: None,
+        }
+    }
+
+    /// Creates a `TimeManagment` that allows searching for exactly `move_time` milliseconds.
+    pub fn fixed(move_time: u128) -> TimeManagment {
+        TimeManagment {
+            move_time: Some(move_time),
+            ..TimeManagment::infinite()
+        }
+    }
+
+    /// Creates a `TimeManagment` without any time limit.
+    pub fn infinite() -> TimeManagment {
+        TimeManagment::new(u128::MAX, 0, None)
+    }
+
+    /// Makes this a `TimeManagment` for a ponder search. The time limits only start to apply once
+    /// the opponent played the expected move.
+    pub fn ponder(self, ponder_hit: Arc<PonderHit>) -> TimeManagment {
+        TimeManagment {
+            ponder_hit: Some(ponder_hit),
+            ..self
+        }
+    }
+
+    /// Returns the time the clock for this move started or `None` if the engine is still
+    /// pondering.
+    fn start_time(&self) -> Option<Instant> {
+        match &self.ponder_hit {
+            Some(ponder_hit) => ponder_hit.time(),
+            None => Some(self.start_time),
         }
     }
 
     pub fn has_time_for_next_iteration(&self, last_iteration_time: u128) -> bool {
+        let Some(start_time) = self.start_time() else {
+            return true;
+        };
+        let elapsed = start_time.elapsed().as_millis();
+
+        // With a fixed move time the search is interrupted once the time is up, so every
+        // millisecond can be used for the next iteration.
+        if let Some(move_time) = self.move_time {
+            return move_time > elapsed;
+        }
+
         let moves_to_go = self.moves_to_go.unwrap_or(24);
-        let elapsed = self.start_time.elapsed().as_millis();
-        let allocated_time = (self.time_left + self.increment) / moves_to_go as u128;
+        let allocated_time = self.time_left.saturating_add(self.increment) / moves_to_go as u128;
         let expected_time = last_iteration_time * 5;
 
         allocated_time > elapsed + expected_time
     }
+
+    /// Checks if the search has to be stopped immediately because the time for this move is up.
+    pub fn is_time_up(&self) -> bool {
+        match (self.move_time, self.start_time()) {
+            (Some(move_time), Some(start_time)) => start_time.elapsed().as_millis() >= move_time,
+            _ => false,
+        }
+    }
+}
+
+/// Signals a ponder search that the opponent played the expected move and the engine's clock is
+/// running.
+#[derive(Debug, Default)]
+pub struct PonderHit(Mutex<Option<Instant>>);
+
+impl PonderHit {
+    pub fn new() -> PonderHit {
+        PonderHit(Mutex::new(None))
+    }
+
+    /// Marks the ponder move as played, which starts the clock.
+    pub fn hit(&self) {
+        let mut time = self.0.lock().unwrap();
+        time.get_or_insert_with(Instant::now);
+    }
+
+    /// Returns the time of the ponder hit or `None` if there was none yet.
+    pub fn time(&self) -> Option<Instant> {
+        *self.0.lock().unwrap()
+    }
+
+    pub fn is_hit(&self) -> bool {
+        self.time().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_time_up() {
+        let time_managment = TimeManagment::fixed(0);
+
+        assert!(time_managment.is_time_up());
+        assert!(!time_managment.has_time_for_next_iteration(0));
+    }
+
+    #[test]
+    fn infinite_never_time_up() {
+        let time_managment = TimeManagment::infinite();
+
+        assert!(!time_managment.is_time_up());
+        assert!(time_managment.has_time_for_next_iteration(1000));
+    }
+
+    #[test]
+    fn ponder_starts_clock_on_hit() {
+        let ponder_hit = Arc::new(PonderHit::new());
+        let time_managment = TimeManagment::fixed(0).ponder(ponder_hit.clone());
+
+        assert!(!time_managment.is_time_up());
+        assert!(time_managment.has_time_for_next_iteration(0));
+
+        ponder_hit.hit();
+
+        assert!(time_managment.is_time_up());
+        assert!(!time_managment.has_time_for_next_iteration(0));
+    }
 }
@@ -1,4 +1,6 @@
-use cherris_core::{Move, Position, Zobrist};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use cherris_core::{Move, Position, Role, Square, Zobrist};
 
 use crate::evaluation::Evaluation;
 
@@ -12,6 +14,103 @@ pub struct TranspositionEntry {
     pub chess_move: Move,
 }
 
+/// Marks a slot of the table as used, so an empty slot never matches a zobrist key of 0.
+const OCCUPIED: u64 = 1 << 63;
+
+impl TranspositionEntry {
+    /// Packs everything but the zobrist key into 64 bits.
+    fn pack(&self) -> u64 {
+        let entry_type = match self.entry_type {
+            TranspositionEntryType::Exact => 0,
+            TranspositionEntryType::UpperBound => 1,
+            TranspositionEntryType::LowerBound => 2,
+        };
+
+        OCCUPIED
+            | self.score.centipawns() as u16 as u64
+            | (self.depth as u64) << 16
+            | entry_type << 24
+            | (pack_move(self.chess_move) as u64) << 26
+    }
+
+    fn unpack(zobrist: Zobrist, data: u64) -> TranspositionEntry {
+        let entry_type = match (data >> 24) & 0b11 {
+            0 => TranspositionEntryType::Exact,
+            1 => TranspositionEntryType::UpperBound,
+            _ => TranspositionEntryType::LowerBound,
+        };
+
+        TranspositionEntry {
+            zobrist,
+            score: Evaluation::new(data as u16 as i16),
+            depth: (data >> 16) as u8,
+            entry_type,
+            chess_move: unpack_move((data >> 26) as u32),
+        }
+    }
+}
+
+/// Packs a move into 30 bits: 2 bits for the kind of move, 12 bits for the squares and 16 bits for
+/// the details of a standard move or the target of an en passant capture.
+fn pack_move(chess_move: Move) -> u32 {
+    let pack_role = |role: Option<Role>| role.map_or(0, |role| role.to_index() as u32 + 1);
+
+    match chess_move {
+        Move::Standard {
+            from,
+            to,
+            role,
+            capture,
+            promotion,
+            en_passant_square,
+        } => {
+            from.to_index() as u32
+                | (to.to_index() as u32) << 6
+                | (role.to_index() as u32) << 12
+                | pack_role(capture) << 15
+                | pack_role(promotion) << 18
+                | en_passant_square.map_or(0, |square| square.to_index() as u32 + 1) << 21
+        }
+        Move::EnPassant { from, to, target } => {
+            1 << 28
+                | from.to_index() as u32
+                | (to.to_index() as u32) << 6
+                | (target.to_index() as u32) << 12
+        }
+        Move::CastleShort => 2 << 28,
+        Move::CastleLong => 3 << 28,
+    }
+}
+
+fn unpack_move(data: u32) -> Move {
+    let square = |shift: u32| Square::from_index((data >> shift) as u8 & 0b111111);
+    let role = |shift: u32| match (data >> shift) & 0b111 {
+        0 => None,
+        role => Some(Role::ALL[role as usize - 1]),
+    };
+
+    match data >> 28 {
+        0 => Move::Standard {
+            from: square(0),
+            to: square(6),
+            role: Role::ALL[(data >> 12) as usize & 0b111],
+            capture: role(15),
+            promotion: role(18),
+            en_passant_square: match (data >> 21) & 0b1111111 {
+                0 => None,
+                square => Some(Square::from_index(square as u8 - 1)),
+            },
+        },
+        1 => Move::EnPassant {
+            from: square(0),
+            to: square(6),
+            target: square(12),
+        },
+        2 => Move::CastleShort,
+        _ => Move::CastleLong,
+    }
+}
+
 /// The type of entry in the transposition table.
 #[derive(Clone, Copy, Debug)]
 pub enum TranspositionEntryType {
@@ -23,65 +122,106 @@ pub enum TranspositionEntryType {
     LowerBound,
 }
 
-#[derive(Clone, Debug)]
+/// A slot of the transposition table. The key is stored xor-ed with the data, so an entry that
+/// was torn by two threads writing at the same time no longer matches its zobrist key and is
+/// ignored instead of returning a wrong move or score.
+#[derive(Debug, Default)]
+struct AtomicEntry {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A transposition table that can be shared between search threads without locking.
+#[derive(Debug)]
 pub struct TranspositionTable {
-    entries: Vec<Option<TranspositionEntry>>,
+    entries: Vec<AtomicEntry>,
     size: u64,
 }
 
 impl TranspositionTable {
     pub fn new(size: u64) -> TranspositionTable {
         TranspositionTable {
-            entries: vec![None; size as usize],
+            entries: (0..size).map(|_| AtomicEntry::default()).collect(),
             size,
         }
     }
 
+    /// Creates a transposition table that uses about `megabytes` of memory.
+    pub fn with_megabytes(megabytes: u64) -> TranspositionTable {
+        let entry_size = size_of::<AtomicEntry>() as u64;
+
+        TranspositionTable::new((megabytes * 1024 * 1024 / entry_size).max(1))
+    }
+
+    /// Removes all entries from the table.
+    pub fn clear(&self) {
+        for entry in self.entries.iter() {
+            entry.key.store(0, Ordering::Relaxed);
+            entry.data.store(0, Ordering::Relaxed);
+        }
+    }
+
     fn get_entry_index(&self, zobrist: Zobrist) -> usize {
         ((zobrist.key() % self.size) & zobrist.key()) as usize
     }
 
-    pub fn get_entry(&self, zobrist: Zobrist) -> &Option<TranspositionEntry> {
-        let key = self.get_entry_index(zobrist);
+    /// Returns the entry for the position with the given zobrist key if the table contains one.
+    pub fn get_entry(&self, zobrist: Zobrist) -> Option<TranspositionEntry> {
+        let index = self.get_entry_index(zobrist);
+        let entry = unsafe { self.entries.get_unchecked(index) };
 
-        self.entries.get(key).unwrap()
+        let key = entry.key.load(Ordering::Relaxed);
+        let data = entry.data.load(Ordering::Relaxed);
+
+        if data & OCCUPIED == 0 || key ^ data != zobrist.key() {
+            return None;
+        }
+
+        Some(TranspositionEntry::unpack(zobrist, data))
     }
 
-    pub fn insert(&mut self, entry: TranspositionEntry) {
-        let key = self.get_entry_index(entry.zobrist);
+    /// Stores an entry of a node at `ply`. Mate scores are stored relative to the node.
+    pub fn insert(&self, entry: TranspositionEntry, ply: u8) {
+        let index = self.get_entry_index(entry.zobrist);
+        let data = TranspositionEntry {
+            score: entry.score.to_transposition_table(ply),
+            ..entry
+        }
+        .pack();
 
-        self.entries[key] = Some(entry);
+        let slot = unsafe { self.entries.get_unchecked(index) };
+        slot.key
+            .store(entry.zobrist.key() ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
     }
 
+    /// Returns the best move stored for the position at `ply` and its score if the score is
+    /// usable for a search with the given window and depth.
     pub fn get(
         &self,
         position: &Position,
         alpha: Evaluation,
         beta: Evaluation,
         depth: u8,
+        ply: u8,
     ) -> (Option<Move>, Option<Evaluation>) {
-        let zobrist = position.zobrist;
-        let key = self.get_entry_index(zobrist);
-
-        let entry = unsafe { self.entries.get_unchecked(key) };
         let mut tt_move = None;
         let mut tt_value = None;
 
-        if let Some(entry) = entry {
-            if entry.zobrist == zobrist {
-                tt_move = Some(entry.chess_move);
-                if entry.depth >= depth {
-                    match entry.entry_type {
-                        TranspositionEntryType::Exact => tt_value = Some(entry.score),
-                        TranspositionEntryType::UpperBound => {
-                            if entry.score <= alpha {
-                                tt_value = Some(entry.score)
-                            }
+        if let Some(entry) = self.get_entry(position.zobrist) {
+            tt_move = Some(entry.chess_move);
+            let score = entry.score.from_transposition_table(ply);
+            if entry.depth >= depth {
+                match entry.entry_type {
+                    TranspositionEntryType::Exact => tt_value = Some(score),
+                    TranspositionEntryType::UpperBound => {
+                        if score <= alpha {
+                            tt_value = Some(score)
                         }
-                        TranspositionEntryType::LowerBound => {
-                            if entry.score >= beta {
-                                tt_value = Some(entry.score)
-                            }
+                    }
+                    TranspositionEntryType::LowerBound => {
+                        if score >= beta {
+                            tt_value = Some(score)
                         }
                     }
                 }
@@ -91,3 +231,98 @@ impl TranspositionTable {
         (tt_move, tt_value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cherris_core::generate_lookup_tables;
+
+    use super::*;
+
+    #[test]
+    fn with_megabytes() {
+        let transposition_table = TranspositionTable::with_megabytes(1);
+        let memory = transposition_table.size as usize * size_of::<AtomicEntry>();
+
+        assert_eq!(memory, 1024 * 1024);
+    }
+
+    #[test]
+    fn clear() {
+        let transposition_table = TranspositionTable::new(16);
+        let zobrist = Position::default().zobrist;
+        transposition_table.insert(
+            TranspositionEntry {
+                zobrist,
+                score: Evaluation::new(0),
+                depth: 1,
+                entry_type: TranspositionEntryType::Exact,
+                chess_move: Move::CastleShort,
+            },
+            0,
+        );
+
+        transposition_table.clear();
+
+        assert!(transposition_table.get_entry(zobrist).is_none());
+    }
+
+    #[test]
+    fn mate_score_relative_to_ply() {
+        let transposition_table = TranspositionTable::new(16);
+        let position = Position::default();
+        transposition_table.insert(
+            TranspositionEntry {
+                zobrist: position.zobrist,
+                score: -Evaluation::new_mate_in(5),
+                depth: 4,
+                entry_type: TranspositionEntryType::Exact,
+                chess_move: Move::CastleShort,
+            },
+            2,
+        );
+
+        // The mate 3 plies after the node is found 2 plies later when the node is reached later.
+        let (_, score) = transposition_table.get(&position, Evaluation::MIN, Evaluation::MAX, 4, 4);
+
+        assert_eq!(score, Some(-Evaluation::new_mate_in(7)));
+    }
+
+    #[test]
+    fn pack_entry() {
+        generate_lookup_tables();
+
+        let position =
+            Position::from_str("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
+                .unwrap();
+        let mut moves = position.legal_moves();
+        moves.push(Move::EnPassant {
+            from: Square::E5,
+            to: Square::D6,
+            target: Square::D5,
+        });
+        moves.push(Move::CastleShort);
+        moves.push(Move::CastleLong);
+
+        for (depth, chess_move) in moves.into_iter().enumerate() {
+            let entry = TranspositionEntry {
+                zobrist: position.zobrist,
+                score: Evaluation::new(-1234),
+                depth: depth as u8,
+                entry_type: TranspositionEntryType::LowerBound,
+                chess_move,
+            };
+
+            let unpacked = TranspositionEntry::unpack(position.zobrist, entry.pack());
+
+            assert_eq!(unpacked.chess_move, chess_move);
+            assert_eq!(unpacked.score, entry.score);
+            assert_eq!(unpacked.depth, entry.depth);
+            assert!(matches!(
+                unpacked.entry_type,
+                TranspositionEntryType::LowerBound
+            ));
+        }
+    }
+}
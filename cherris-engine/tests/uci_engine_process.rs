@@ -0,0 +1,105 @@
+use std::{io::ErrorKind, time::Duration};
+
+use cherris_core::uci::{
+    UCIEngineCommand, UCIGoParams, UCIGuiCommand, UCIOptionType, UCIPositionParams,
+    UciEngineProcess,
+};
+
+const ENGINE: &str = env!("CARGO_BIN_EXE_cherris-engine");
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[test]
+fn handshake() {
+    let engine = UciEngineProcess::spawn(ENGINE).unwrap();
+
+    assert_eq!(engine.name(), Some("cherris"));
+    assert_eq!(engine.author(), Some("Johannes Thiel"));
+
+    let hash = engine
+        .options()
+        .iter()
+        .find(|option| option.id == "Hash")
+        .unwrap();
+    assert_eq!(hash.option_type, UCIOptionType::Spin);
+    assert!(
+        engine
+            .options()
+            .iter()
+            .any(|option| option.id == "Clear Hash" && option.option_type == UCIOptionType::Button)
+    );
+}
+
+#[test]
+fn search() {
+    let mut engine = UciEngineProcess::spawn(ENGINE).unwrap();
+
+    engine
+        .send(&UCIEngineCommand::Position(UCIPositionParams::startpos(
+            vec!["e2e4".to_string()],
+        )))
+        .unwrap();
+    engine
+        .send(&UCIEngineCommand::Go(UCIGoParams {
+            depth: Some(4),
+            ..Default::default()
+        }))
+        .unwrap();
+
+    let mut infos = Vec::new();
+    let best_move = loop {
+        match engine.recv_timeout(SEARCH_TIMEOUT).unwrap() {
+            UCIGuiCommand::Info(info) => infos.push(info),
+            UCIGuiCommand::BestMove { chess_move, .. } => break chess_move,
+            command => panic!("unexpected command {}", command),
+        }
+    };
+
+    let last_info = infos.last().unwrap();
+    assert_eq!(last_info.depth, Some(4));
+    assert!(last_info.score.is_some());
+    assert_eq!(last_info.pv.first(), Some(&best_move));
+    assert!(infos.iter().all(|info| info.nodes.is_some()));
+}
+
+#[test]
+fn stop_infinite_search() {
+    let mut engine = UciEngineProcess::spawn(ENGINE).unwrap();
+
+    engine
+        .send(&UCIEngineCommand::Position(UCIPositionParams::startpos(
+            vec![],
+        )))
+        .unwrap();
+    engine
+        .send(&UCIEngineCommand::Go(UCIGoParams {
+            infinite: true,
+            ..Default::default()
+        }))
+        .unwrap();
+    engine
+        .wait_for(SEARCH_TIMEOUT, |command| {
+            matches!(command, UCIGuiCommand::Info(_))
+        })
+        .unwrap();
+
+    assert!(matches!(
+        engine.stop().unwrap(),
+        UCIGuiCommand::BestMove { .. }
+    ));
+    engine.is_ready().unwrap();
+}
+
+#[test]
+fn exited_engine() {
+    let mut engine = UciEngineProcess::spawn(ENGINE).unwrap();
+
+    assert!(engine.try_recv().unwrap().is_none());
+
+    engine.send(&UCIEngineCommand::Quit).unwrap();
+    let err = engine.recv_timeout(SEARCH_TIMEOUT).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(
+        engine.try_recv().unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
@@ -0,0 +1,103 @@
+use std::io;
+
+use cherris_core::{
+    Color, Game, GameResult, Zobrist,
+    uci::{
+        UCIEngineCommand, UCIGoParams, UCIGuiCommand, UCIScore, UCIScoreBound, UCISearchInfo,
+        UciEngineProcess,
+    },
+};
+
+/// The latest result of the analysis.
+pub struct AnalysisInfo {
+    pub depth: u8,
+    /// The score from the view of white.
+    pub score: UCIScore,
+    pub pv: Vec<String>,
+}
+
+/// Lets an external engine analyse the position on the board for as long as it doesn't change.
+pub struct Analysis {
+    engine: UciEngineProcess,
+    /// The position that is analysed.
+    analysed: Option<Zobrist>,
+    /// The position that is analysed once the engine stopped searching the previous one.
+    pending: Option<UCIEngineCommand>,
+    is_searching: bool,
+    /// Whether the engine was told to stop but didn't send its best move yet.
+    is_stopping: bool,
+    info: Option<AnalysisInfo>,
+}
+
+impl Analysis {
+    pub fn new(engine: UciEngineProcess) -> Analysis {
+        Analysis {
+            engine,
+            analysed: None,
+            pending: None,
+            is_searching: false,
+            is_stopping: false,
+            info: None,
+        }
+    }
+
+    pub fn info(&self) -> Option<&AnalysisInfo> {
+        self.info.as_ref()
+    }
+
+    /// Restarts the analysis if the position on the board changed and reads the output of the
+    /// engine. Fails if the engine can't be reached anymore.
+    pub fn update(&mut self, game: &Game) -> io::Result<()> {
+        let position = game.position();
+        if self.analysed != Some(position.zobrist) {
+            self.analysed = Some(position.zobrist);
+            self.info = None;
+            // There is nothing to analyse once the game is over.
+            self.pending =
+                (game.result() == GameResult::Ongoing).then(|| UCIEngineCommand::position_of(game));
+            if self.is_searching && !self.is_stopping {
+                self.engine.send(&UCIEngineCommand::Stop)?;
+                self.is_stopping = true;
+            }
+        }
+
+        while let Some(command) = self.engine.try_recv()? {
+            match command {
+                // Infos that arrive after the position changed still belong to the old one. Infos
+                // with only a bound or without a score are skipped.
+                UCIGuiCommand::Info(UCISearchInfo {
+                    depth: Some(depth),
+                    score: Some(score),
+                    bound: UCIScoreBound::Exact,
+                    pv,
+                    ..
+                }) if !self.is_stopping => {
+                    let score = match (position.color_to_move, score) {
+                        (Color::White, score) => score,
+                        (Color::Black, UCIScore::Centipawns(cp)) => UCIScore::Centipawns(-cp),
+                        (Color::Black, UCIScore::Mate(mate)) => UCIScore::Mate(-mate),
+                    };
+                    self.info = Some(AnalysisInfo { depth, score, pv });
+                }
+                UCIGuiCommand::BestMove { .. } => {
+                    self.is_searching = false;
+                    self.is_stopping = false;
+                }
+                _ => {}
+            }
+        }
+
+        if !self.is_searching
+            && let Some(position_command) = self.pending.take()
+        {
+            self.engine.send(&position_command)?;
+            self.engine.send(&UCIEngineCommand::Go(UCIGoParams {
+                infinite: true,
+                ..Default::default()
+            }))?;
+            self.is_searching = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use cherris_core::{
+    Color, Game, GameAction, GameResult, Move, Position, Zobrist,
+    uci::{UCIEngineCommand, UCIGoParams, UCIGuiCommand, UciEngineProcess},
+};
+use cherris_engine::{
+    SearchLimits, iterative_deepening::iterative_deepening, transposition_table::TranspositionTable,
+};
+
+/// The size of the transposition table of the engine in megabytes.
+const HASH_SIZE: u64 = 64;
+
+/// The engine that searches the moves of an `EnginePlayer`.
+enum Backend {
+    /// Cherris, searching on its own thread.
+    Cherris {
+        transposition_table: Arc<TranspositionTable>,
+        stop: Arc<AtomicBool>,
+        search: Option<JoinHandle<Vec<Move>>>,
+    },
+    /// An external engine, together with the position it is searching.
+    Uci {
+        engine: UciEngineProcess,
+        position: Position,
+    },
+}
+
+/// Lets an engine play one color of a game. The search runs outside of the render thread, so the
+/// board keeps rendering while the engine thinks.
+pub struct EnginePlayer {
+    pub color: Color,
+    /// The time the engine spends on each move in milliseconds.
+    movetime: u64,
+    backend: Backend,
+    /// The time the current search was started at.
+    search_start: Option<Instant>,
+}
+
+impl EnginePlayer {
+    pub fn new(color: Color, movetime: u64) -> EnginePlayer {
+        EnginePlayer {
+            color,
+            movetime,
+            backend: Backend::Cherris {
+                transposition_table: Arc::new(TranspositionTable::with_megabytes(HASH_SIZE)),
+                stop: Arc::new(AtomicBool::new(false)),
+                search: None,
+            },
+            search_start: None,
+        }
+    }
+
+    /// Lets an external UCI engine play instead of cherris.
+    pub fn external(color: Color, movetime: u64, engine: UciEngineProcess) -> EnginePlayer {
+        EnginePlayer {
+            color,
+            movetime,
+            backend: Backend::Uci {
+                engine,
+                position: Position::default(),
+            },
+            search_start: None,
+        }
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.search_start.is_some()
+    }
+
+    /// Returns how much of its time for the current move the engine has used, between 0 and 1.
+    pub fn progress(&self) -> Option<f32> {
+        self.search_start.map(|start| {
+            (start.elapsed().as_millis() as f32 / self.movetime.max(1) as f32).min(1.0)
+        })
+    }
+
+    /// Starts searching the current position of `game` if it is the engine's turn. Fails if an
+    /// external engine can't be reached anymore.
+    pub fn start(&mut self, game: &Game) -> io::Result<()> {
+        if self.is_thinking()
+            || game.color_to_move() != self.color
+            || game.result() != GameResult::Ongoing
+        {
+            return Ok(());
+        }
+
+        let search_params = UCIGoParams {
+            movetime: Some(self.movetime),
+            ..Default::default()
+        };
+
+        match &mut self.backend {
+            Backend::Cherris {
+                transposition_table,
+                stop,
+                search,
+            } => {
+                let position = *game.position();
+                let history = zobrist_history(game);
+                let search_limits = SearchLimits::new(&position, &search_params);
+                let transposition_table = transposition_table.clone();
+                *stop = Arc::new(AtomicBool::new(false));
+                let stop = stop.clone();
+
+                *search = Some(thread::spawn(move || {
+                    iterative_deepening(
+                        position,
+                        &history,
+                        &search_limits,
+                        transposition_table,
+                        stop,
+                        Arc::new(AtomicU64::new(0)),
+                        0,
+                        |_| {},
+                    )
+                }));
+            }
+            Backend::Uci { engine, position } => {
+                *position = *game.position();
+                engine.send(&UCIEngineCommand::position_of(game))?;
+                engine.send(&UCIEngineCommand::Go(search_params))?;
+            }
+        }
+        self.search_start = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Returns the move of the engine once its search is done. An engine that sends an illegal
+    /// move or exits forfeits the game and resigns instead.
+    pub fn poll(&mut self) -> Option<GameAction> {
+        if !self.is_thinking() {
+            return None;
+        }
+
+        let action = match &mut self.backend {
+            Backend::Cherris { search, .. } => {
+                if !search.as_ref().is_some_and(|search| search.is_finished()) {
+                    return None;
+                }
+
+                let pv = search.take()?.join().expect("Search thread panicked");
+                pv.first().copied().map(GameAction::Move)
+            }
+            Backend::Uci { engine, position } => loop {
+                let command = match engine.try_recv() {
+                    Ok(command) => command?,
+                    Err(err) => {
+                        eprintln!("The engine stopped ({}) and forfeits the game", err);
+                        break Some(GameAction::Resign);
+                    }
+                };
+                match command {
+                    UCIGuiCommand::BestMove {
+                        chess_move: uci_move,
+                        ..
+                    } => match Move::from_uci(&uci_move, position) {
+                        Some(chess_move) => break Some(GameAction::Move(chess_move)),
+                        None => {
+                            eprintln!(
+                                "The engine sent the illegal move {} and forfeits the game",
+                                uci_move
+                            );
+                            break Some(GameAction::Resign);
+                        }
+                    },
+                    _ => continue,
+                }
+            },
+        };
+        self.search_start = None;
+
+        action
+    }
+
+    /// Stops a running search and throws away its result, for example when the game is reset.
+    pub fn cancel(&mut self) {
+        if self.search_start.take().is_none() {
+            return;
+        }
+
+        match &mut self.backend {
+            Backend::Cherris { stop, search, .. } => {
+                if let Some(search) = search.take() {
+                    stop.store(true, Ordering::Relaxed);
+                    search.join().expect("Search thread panicked");
+                }
+            }
+            Backend::Uci { engine, .. } => {
+                if let Err(err) = engine.stop() {
+                    eprintln!("Failed to stop the engine: {}", err);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for EnginePlayer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Returns the zobrist keys of all positions before the current one, which the search needs to
+/// detect repetitions.
+fn zobrist_history(game: &Game) -> Vec<Zobrist> {
+    let mut position = *game.starting_position();
+
+    game.move_history()
+        .iter()
+        .map(|mv| {
+            let zobrist = position.zobrist;
+            position.make_move(*mv);
+            zobrist
+        })
+        .collect()
+}
@@ -2,25 +2,69 @@ use sdl2::{
     image::{InitFlag, LoadTexture},
     mouse::MouseButton,
     rect::{Point, Rect},
-    render::Texture,
+    render::{BlendMode, Texture},
     video::Window,
 };
-use std::time::Duration;
+use std::{process, str::FromStr, time::Duration};
 
-use cherris_core::{File, Game, Move, Piece, Position, Rank, Role, Square, generate_lookup_tables};
+use cherris_core::{
+    File, Game, GameAction, GameResult, LAN, Move, Piece, Position, Rank, Role, Square,
+    generate_lookup_tables,
+    uci::{UCIScore, UciEngineProcess},
+};
 use sdl2::{event::Event, keyboard::Keycode, pixels::Color, render::Canvas};
 
-const WINDOW_WIDTH: u32 = 800;
+use crate::{
+    analysis::{Analysis, AnalysisInfo},
+    engine_player::EnginePlayer,
+    promotion::PromotionPicker,
+    settings::{Settings, USAGE},
+};
+
+mod analysis;
+mod engine_player;
+mod promotion;
+mod settings;
+
+const BOARD_SIZE: u32 = 800;
 const WINDOW_HEIGHT: u32 = 800;
-const SQAURE_SIZE: u32 = WINDOW_WIDTH / 8;
+const SQAURE_SIZE: u32 = BOARD_SIZE / 8;
+const TITLE: &str = "Cherris";
+
+/// The height of the bar that shows how long the engine has been thinking.
+const THINKING_BAR_HEIGHT: u32 = 6;
+/// The width of the evaluation bar next to the board while the position is analysed.
+const EVAL_BAR_WIDTH: u32 = 40;
+/// The number of centipawns at which the evaluation bar is almost full.
+const EVAL_BAR_SCALE: f32 = 400.0;
+/// The number of moves of the analysed line shown in the title.
+const TITLE_PV_LENGTH: usize = 5;
 
 fn main() {
+    let settings = Settings::from_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(2);
+    });
+
+    let spawn_uci_engine = || {
+        let path = settings.uci_engine.as_deref().unwrap_or_default();
+        UciEngineProcess::spawn(path).unwrap_or_else(|err| {
+            eprintln!("Failed to start {}: {}", path, err);
+            process::exit(1);
+        })
+    };
+    let mut analysis = settings.analyse.then(|| Analysis::new(spawn_uci_engine()));
+    let window_width = match analysis {
+        Some(_) => BOARD_SIZE + EVAL_BAR_WIDTH,
+        None => BOARD_SIZE,
+    };
+
     let sdl_context = sdl2::init().unwrap();
     let _sld_image = sdl2::image::init(InitFlag::PNG).unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
     let window = video_subsystem
-        .window("Cherris", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window(TITLE, window_width, WINDOW_HEIGHT)
         .position_centered()
         .build()
         .unwrap();
@@ -38,8 +82,17 @@ fn main() {
     let mut dragged_piece: Option<Piece> = None;
     let mut dragged_starting_sqaure: Option<Square> = None;
     let mut mouse_position = Point::new(0, 0);
+    let mut promotion_picker: Option<PromotionPicker> = None;
+    // Promotes to a queen right away instead of opening the promotion picker.
+    let mut auto_queen = false;
 
     let mut moves = game.moves();
+    let mut engine = settings
+        .engine_color
+        .map(|color| match settings.uci_engine {
+            Some(_) => EnginePlayer::external(color, settings.movetime, spawn_uci_engine()),
+            None => EnginePlayer::new(color, settings.movetime),
+        });
 
     let mut event_pump = sdl_context.event_pump().unwrap();
     'running: loop {
@@ -59,15 +112,47 @@ fn main() {
             });
             draw_possible_moves(&mut canvas, piece_moves, game.color_to_move());
         }
+        if let Some(info) = analysis.as_ref().and_then(Analysis::info) {
+            draw_best_move(&mut canvas, info);
+            draw_eval_bar(&mut canvas, info);
+        }
         draw_position(
             &mut canvas,
             &texture,
             game.position(),
             dragged_starting_sqaure,
         );
+        if let Some(picker) = &promotion_picker {
+            draw_promotion_picker(&mut canvas, &texture, picker);
+        }
 
         for event in event_pump.poll_iter() {
             match event {
+                // The promotion picker takes the keyboard while it is open.
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if promotion_picker.is_some() => {
+                    let role = match keycode {
+                        Keycode::Q => Some(Role::Queen),
+                        Keycode::R => Some(Role::Rook),
+                        Keycode::B => Some(Role::Bishop),
+                        Keycode::N => Some(Role::Knight),
+                        _ => None,
+                    };
+
+                    if let Some(chess_move) = promotion_picker
+                        .as_ref()
+                        .zip(role)
+                        .and_then(|(picker, role)| picker.choose(role))
+                    {
+                        play_move(&mut game, chess_move);
+                        moves = game.moves();
+                        promotion_picker = None;
+                    } else if keycode == Keycode::Escape {
+                        promotion_picker = None;
+                    }
+                }
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
@@ -77,9 +162,16 @@ fn main() {
                     keycode: Some(Keycode::R),
                     ..
                 } => {
+                    if let Some(engine) = &mut engine {
+                        engine.cancel();
+                    }
                     game = Game::new();
                     moves = game.moves();
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::A),
+                    ..
+                } => auto_queen = !auto_queen,
                 Event::MouseMotion { x, y, .. } => {
                     hovered_square = None;
                     mouse_position = Point::new(x, y);
@@ -102,71 +194,121 @@ fn main() {
                         }
                     }
                 }
-                Event::MouseButtonDown { mouse_btn, .. } => {
-                    if mouse_btn == MouseButton::Left {
-                        if let Some(square) = hovered_square {
-                            if let Some(piece) = game.position().board.piece_on(square) {
-                                if piece.color == game.color_to_move() {
-                                    dragged_starting_sqaure = hovered_square;
-                                    dragged_piece = Some(piece);
-                                }
-                            }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => {
+                    let is_engine_turn = engine
+                        .as_ref()
+                        .is_some_and(|engine| engine.color == game.color_to_move());
+                    // A click next to the promotion picker takes the move back.
+                    if let Some(picker) = promotion_picker.take() {
+                        if let Some(chess_move) = picker.choice_at(mouse_position) {
+                            play_move(&mut game, chess_move);
+                            moves = game.moves();
                         }
+                    } else if !is_engine_turn
+                        && let Some(square) = hovered_square
+                        && let Some(piece) = game.position().board.piece_on(square)
+                        && piece.color == game.color_to_move()
+                    {
+                        dragged_starting_sqaure = hovered_square;
+                        dragged_piece = Some(piece);
                     }
                 }
-                Event::MouseButtonUp { mouse_btn, .. } => {
-                    if mouse_btn == MouseButton::Left {
-                        if dragged_piece.is_some() {
-                            if let Some(sqaure) = dragged_starting_sqaure {
-                                if let Some(hoverd_sqaure) = hovered_square {
-                                    let mut piece_moves = moves.to_vec();
-                                    piece_moves.retain(|m| match m {
-                                        Move::Standard { from, to, .. } => {
-                                            *from == sqaure && hoverd_sqaure == *to
-                                        }
-                                        Move::EnPassant { from, to, .. } => {
-                                            *from == sqaure && hoverd_sqaure == *to
-                                        }
-                                        Move::CastleShort => {
-                                            let to = match game.color_to_move() {
-                                                cherris_core::Color::White => Square::G1,
-                                                cherris_core::Color::Black => Square::G8,
-                                            };
-                                            hoverd_sqaure == to
-                                        }
-                                        Move::CastleLong => {
-                                            let to = match game.color_to_move() {
-                                                cherris_core::Color::White => Square::C1,
-                                                cherris_core::Color::Black => Square::C8,
-                                            };
-                                            hoverd_sqaure == to
-                                        }
-                                    });
-
-                                    if let Some(choosen_move) = piece_moves.first() {
-                                        game.make_move(*choosen_move);
-                                        moves = game.moves();
-                                        if moves.is_empty() {
-                                            match game.color_to_move() {
-                                                cherris_core::Color::White => {
-                                                    println!("Checkmate! Black wins!")
-                                                }
-                                                cherris_core::Color::Black => {
-                                                    println!("Checkmate! White wins!")
-                                                }
-                                            }
-                                        }
-                                    }
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => {
+                    if dragged_piece.is_some()
+                        && let Some(sqaure) = dragged_starting_sqaure
+                        && let Some(hoverd_sqaure) = hovered_square
+                    {
+                        let mut piece_moves = moves.to_vec();
+                        piece_moves.retain(|m| match m {
+                            Move::Standard { from, to, .. } => {
+                                *from == sqaure && hoverd_sqaure == *to
+                            }
+                            Move::EnPassant { from, to, .. } => {
+                                *from == sqaure && hoverd_sqaure == *to
+                            }
+                            Move::CastleShort => {
+                                let to = match game.color_to_move() {
+                                    cherris_core::Color::White => Square::G1,
+                                    cherris_core::Color::Black => Square::G8,
+                                };
+                                hoverd_sqaure == to
+                            }
+                            Move::CastleLong => {
+                                let to = match game.color_to_move() {
+                                    cherris_core::Color::White => Square::C1,
+                                    cherris_core::Color::Black => Square::C8,
+                                };
+                                hoverd_sqaure == to
+                            }
+                        });
+
+                        match PromotionPicker::new(&piece_moves, game.color_to_move()) {
+                            Some(picker) if auto_queen => {
+                                if let Some(queen_promotion) = picker.choose(Role::Queen) {
+                                    play_move(&mut game, queen_promotion);
+                                    moves = game.moves();
+                                }
+                            }
+                            Some(picker) => promotion_picker = Some(picker),
+                            None => {
+                                if let Some(choosen_move) = piece_moves.first() {
+                                    play_move(&mut game, *choosen_move);
+                                    moves = game.moves();
                                 }
                             }
                         }
-                        dragged_starting_sqaure = None;
-                        dragged_piece = None;
                     }
+                    dragged_starting_sqaure = None;
+                    dragged_piece = None;
                 }
                 _ => {}
             }
         }
+        if let Some(engine) = &mut engine {
+            if let Some(action) = engine.poll() {
+                match action {
+                    GameAction::Move(engine_move) => play_move(&mut game, engine_move),
+                    action => {
+                        if let Err(err) = game.apply(engine.color, action) {
+                            eprintln!("Failed to end the game for the engine: {:?}", err);
+                        }
+                    }
+                }
+                moves = game.moves();
+            }
+            if let Err(err) = engine.start(&game) {
+                eprintln!("The engine stopped ({}) and forfeits the game", err);
+                game.apply(engine.color, GameAction::Resign)
+                    .expect("The engine only starts while the game is ongoing");
+            }
+
+            if let Some(progress) = engine.progress() {
+                draw_thinking_bar(&mut canvas, progress);
+            }
+        }
+
+        if let Some(err) = analysis
+            .as_mut()
+            .and_then(|analysis| analysis.update(&game).err())
+        {
+            eprintln!(
+                "The analysis engine stopped ({}), so the analysis ends",
+                err
+            );
+            analysis = None;
+        }
+
+        let title = window_title(engine.as_ref(), analysis.as_ref(), auto_queen);
+        if canvas.window().title() != title {
+            canvas.window_mut().set_title(&title).unwrap();
+        }
+
         // The rest of the game loop goes here...
         if let Some(piece) = dragged_piece {
             let piece_position = Point::new(
@@ -181,6 +323,145 @@ fn main() {
     }
 }
 
+/// Plays a move and announces the end of the game.
+fn play_move(game: &mut Game, chess_move: Move) {
+    game.make_move(chess_move);
+
+    match game.result() {
+        GameResult::Win(cherris_core::Color::White) => println!("Checkmate! White wins!"),
+        GameResult::Win(cherris_core::Color::Black) => println!("Checkmate! Black wins!"),
+        GameResult::Draw(reason) => println!("Draw by {}!", reason),
+        _ => {}
+    }
+}
+
+/// Returns the title of the window, which tells if the engine is thinking and shows the analysis.
+fn window_title(
+    engine: Option<&EnginePlayer>,
+    analysis: Option<&Analysis>,
+    auto_queen: bool,
+) -> String {
+    let mut title = TITLE.to_string();
+
+    if auto_queen {
+        title.push_str(" - auto-queen");
+    }
+
+    if engine.is_some_and(EnginePlayer::is_thinking) {
+        title.push_str(" - thinking...");
+    }
+
+    if let Some(info) = analysis.and_then(Analysis::info) {
+        let score = match info.score {
+            UCIScore::Centipawns(centipawns) => format!("{:+.2}", centipawns as f64 / 100.0),
+            UCIScore::Mate(mate) if mate >= 0 => format!("+M{}", mate),
+            UCIScore::Mate(mate) => format!("-M{}", -mate),
+        };
+        let pv = info
+            .pv
+            .iter()
+            .take(TITLE_PV_LENGTH)
+            .cloned()
+            .collect::<Vec<_>>();
+        title.push_str(&format!(
+            " - depth {} {} {}",
+            info.depth,
+            score,
+            pv.join(" ")
+        ));
+    }
+
+    title
+}
+
+/// Draws a bar next to the board that shows how much better white stands in the analysis.
+fn draw_eval_bar(canvas: &mut Canvas<Window>, info: &AnalysisInfo) {
+    let white_share = match info.score {
+        UCIScore::Centipawns(centipawns) => 0.5 + 0.5 * (centipawns as f32 / EVAL_BAR_SCALE).tanh(),
+        UCIScore::Mate(mate) if mate > 0 => 1.0,
+        UCIScore::Mate(_) => 0.0,
+    };
+    let white_height = (WINDOW_HEIGHT as f32 * white_share) as u32;
+
+    canvas.set_draw_color(Color::RGB(40, 40, 40));
+    canvas
+        .fill_rect(Rect::new(
+            BOARD_SIZE as i32,
+            0,
+            EVAL_BAR_WIDTH,
+            WINDOW_HEIGHT - white_height,
+        ))
+        .unwrap();
+    canvas.set_draw_color(Color::RGB(230, 230, 230));
+    canvas
+        .fill_rect(Rect::new(
+            BOARD_SIZE as i32,
+            (WINDOW_HEIGHT - white_height) as i32,
+            EVAL_BAR_WIDTH,
+            white_height,
+        ))
+        .unwrap();
+}
+
+/// Highlights the squares of the best move found by the analysis.
+fn draw_best_move(canvas: &mut Canvas<Window>, info: &AnalysisInfo) {
+    let Some(lan) = info
+        .pv
+        .first()
+        .filter(|mv| mv.len() >= 4)
+        .and_then(|mv| LAN::from_str(mv).ok())
+    else {
+        return;
+    };
+
+    canvas.set_draw_color(Color::RGB(100, 160, 220));
+    for square in [lan.from, lan.to] {
+        let file_index = square.to_index() % 8;
+        let rank_index = square.to_index() / 8;
+        canvas
+            .fill_rect(Rect::new(
+                (SQAURE_SIZE * file_index as u32).try_into().unwrap(),
+                (SQAURE_SIZE * (7 - rank_index) as u32).try_into().unwrap(),
+                SQAURE_SIZE,
+                SQAURE_SIZE,
+            ))
+            .unwrap();
+    }
+}
+
+/// Dims the board and draws the pieces a pawn can promote to on top of it.
+fn draw_promotion_picker(
+    canvas: &mut Canvas<Window>,
+    piece_texture: &Texture,
+    picker: &PromotionPicker,
+) {
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 140));
+    canvas
+        .fill_rect(Rect::new(0, 0, BOARD_SIZE, BOARD_SIZE))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+
+    for (piece, rect) in picker.choices() {
+        canvas.set_draw_color(Color::RGB(230, 230, 230));
+        canvas.fill_rect(rect).unwrap();
+        draw_piece(canvas, piece_texture, piece, rect.top_left());
+    }
+}
+
+/// Draws a bar at the top of the board that fills up while the engine uses its time.
+fn draw_thinking_bar(canvas: &mut Canvas<Window>, progress: f32) {
+    let width = (BOARD_SIZE as f32 * progress) as u32;
+    if width == 0 {
+        return;
+    }
+
+    canvas.set_draw_color(Color::RGB(70, 130, 180));
+    canvas
+        .fill_rect(Rect::new(0, 0, width, THINKING_BAR_HEIGHT))
+        .unwrap();
+}
+
 fn draw_possible_moves(
     canvas: &mut Canvas<Window>,
     moves: Vec<Move>,
@@ -0,0 +1,85 @@
+use cherris_core::{Color, Move, Piece, Role, Square};
+use sdl2::rect::{Point, Rect};
+
+use crate::SQAURE_SIZE;
+
+/// The roles a pawn can promote to, in the order the picker shows them.
+const PROMOTION_ROLES: [Role; 4] = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight];
+
+/// Lets the player choose the piece a pawn promotes to. The choices are shown in the file of the
+/// promotion, starting at the promotion square and going towards the center of the board.
+pub struct PromotionPicker {
+    /// The promotion moves to the same square, one for each role.
+    moves: Vec<Move>,
+    to: Square,
+    color: Color,
+}
+
+impl PromotionPicker {
+    /// Returns a picker if `moves`, the legal moves of a dropped piece to one square, are
+    /// promotions.
+    pub fn new(moves: &[Move], color: Color) -> Option<PromotionPicker> {
+        let to = match moves.first()? {
+            Move::Standard {
+                to,
+                promotion: Some(_),
+                ..
+            } => *to,
+            _ => return None,
+        };
+
+        Some(PromotionPicker {
+            moves: moves.to_vec(),
+            to,
+            color,
+        })
+    }
+
+    /// Returns the promotion to `role`.
+    pub fn choose(&self, role: Role) -> Option<Move> {
+        self.moves
+            .iter()
+            .find(|chess_move| {
+                matches!(chess_move, Move::Standard { promotion: Some(promotion), .. } if *promotion == role)
+            })
+            .copied()
+    }
+
+    /// Returns the pieces that can be chosen together with the squares they are drawn on.
+    pub fn choices(&self) -> impl Iterator<Item = (Piece, Rect)> + '_ {
+        let file_index = (self.to.to_index() % 8) as i32;
+        let rank_index = (self.to.to_index() / 8) as i32;
+        let direction = match self.color {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+
+        PROMOTION_ROLES
+            .iter()
+            .enumerate()
+            .map(move |(index, role)| {
+                let rank_index = rank_index + direction * index as i32;
+                let rect = Rect::new(
+                    SQAURE_SIZE as i32 * file_index,
+                    SQAURE_SIZE as i32 * (7 - rank_index),
+                    SQAURE_SIZE,
+                    SQAURE_SIZE,
+                );
+
+                (
+                    Piece {
+                        color: self.color,
+                        role: *role,
+                    },
+                    rect,
+                )
+            })
+    }
+
+    /// Returns the promotion whose piece is drawn at `point`.
+    pub fn choice_at(&self, point: Point) -> Option<Move> {
+        self.choices()
+            .find(|(_, rect)| rect.contains_point(point))
+            .and_then(|(piece, _)| self.choose(piece.role))
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+
+use cherris_core::Color;
+
+/// The time the engine spends on a move if none is given.
+const DEFAULT_MOVETIME: u64 = 1000;
+
+pub const USAGE: &str = "usage: cherris-game [--engine <white|black>] [--movetime <ms>] \
+[--uci-engine <path>] [--analyse] [--config <path>]";
+
+/// The settings the game is started with.
+pub struct Settings {
+    /// The color the engine plays. Without one both colors are played on the same board.
+    pub engine_color: Option<Color>,
+    /// The time the engine spends on each move in milliseconds.
+    pub movetime: u64,
+    /// An external UCI engine that is played against instead of cherris and that analyses the
+    /// game with `analyse`.
+    pub uci_engine: Option<String>,
+    /// Whether the external engine analyses the position on the board.
+    pub analyse: bool,
+}
+
+impl Settings {
+    /// Reads the settings from the command line. A config file given with `--config` contains
+    /// one setting per line, written like the arguments but without the dashes, for example
+    /// `uci-engine = /usr/bin/stockfish`. Empty lines and lines starting with `#` are ignored.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+        let mut settings = Settings {
+            engine_color: None,
+            movetime: DEFAULT_MOVETIME,
+            uci_engine: None,
+            analyse: false,
+        };
+
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unknown argument {}", arg))?;
+            let value = match key {
+                "analyse" => None,
+                _ => Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing value for {}", arg))?,
+                ),
+            };
+
+            if key == "config" {
+                settings.read_config(value.as_deref().unwrap_or_default())?;
+            } else {
+                settings.set(key, value.as_deref())?;
+            }
+        }
+
+        if settings.analyse && settings.uci_engine.is_none() {
+            return Err("analyse needs a uci-engine".to_string());
+        }
+
+        Ok(settings)
+    }
+
+    fn read_config(&mut self, path: &str) -> Result<(), String> {
+        let config =
+            fs::read_to_string(path).map_err(|err| format!("can't read {}: {}", path, err))?;
+
+        for line in config.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (line, None),
+            };
+            self.set(key, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: Option<&str>) -> Result<(), String> {
+        let value = value.unwrap_or_default();
+
+        match key {
+            "engine" => {
+                self.engine_color = match value {
+                    "white" => Some(Color::White),
+                    "black" => Some(Color::Black),
+                    _ => return Err(format!("invalid color {}", value)),
+                }
+            }
+            "movetime" => {
+                self.movetime = value
+                    .parse()
+                    .map_err(|_| format!("invalid movetime {}", value))?
+            }
+            "uci-engine" => self.uci_engine = Some(value.to_string()),
+            "analyse" => {
+                self.analyse = match value {
+                    "" | "true" => true,
+                    "false" => false,
+                    _ => return Err(format!("invalid value {} for analyse", value)),
+                }
+            }
+            _ => return Err(format!("unknown setting {}", key)),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use cherris_core::{Color, uci::UCIScore};
+
+/// The centipawns a mate score counts as.
+const MATE_CENTIPAWNS: i32 = 100_000;
+
+/// Lets an engine resign once its score stayed at or below `-score` centipawns for `moves` of its
+/// moves in a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResignAdjudication {
+    pub moves: u32,
+    pub score: i32,
+}
+
+/// Declares a draw once the game is at least `move_number` moves long and the scores of both
+/// engines stayed within `score` centipawns of 0 for `moves` moves in a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawAdjudication {
+    pub move_number: u32,
+    pub moves: u32,
+    pub score: i32,
+}
+
+/// The rules that end games before the rules of chess do.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AdjudicationSettings {
+    pub resign: Option<ResignAdjudication>,
+    pub draw: Option<DrawAdjudication>,
+    /// The number of moves after which a game is a draw.
+    pub max_moves: Option<u32>,
+}
+
+/// The way a game was adjudicated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Adjudication {
+    /// The engine of the color lost.
+    Resign(Color),
+    Draw,
+    /// The game reached the move limit.
+    MaxMoves,
+}
+
+/// Follows the scores of the engines during a game to adjudicate it.
+pub struct Adjudicator {
+    settings: AdjudicationSettings,
+    /// The number of moves in a row each engine thought it was lost.
+    losing_moves: [u32; Color::COUNT],
+    /// The number of moves in a row both engines thought the game was a draw.
+    drawn_plies: u32,
+}
+
+impl Adjudicator {
+    pub fn new(settings: AdjudicationSettings) -> Adjudicator {
+        Adjudicator {
+            settings,
+            losing_moves: [0; Color::COUNT],
+            drawn_plies: 0,
+        }
+    }
+
+    /// Records the score the engine of `color` reported for the move it just played, from its own
+    /// point of view, and adjudicates the game if one of the rules applies. `plies` is the length
+    /// of the game so far.
+    pub fn update(
+        &mut self,
+        color: Color,
+        score: Option<UCIScore>,
+        plies: usize,
+    ) -> Option<Adjudication> {
+        let centipawns = score.map(|score| match score {
+            UCIScore::Centipawns(centipawns) => centipawns as i32,
+            UCIScore::Mate(mate) if mate > 0 => MATE_CENTIPAWNS,
+            UCIScore::Mate(_) => -MATE_CENTIPAWNS,
+        });
+        let moves = (plies / 2) as u32;
+
+        if let Some(resign) = self.settings.resign {
+            match centipawns {
+                Some(centipawns) if centipawns <= -resign.score => self.losing_moves[color] += 1,
+                _ => self.losing_moves[color] = 0,
+            }
+
+            if self.losing_moves[color] >= resign.moves {
+                return Some(Adjudication::Resign(color));
+            }
+        }
+
+        if let Some(draw) = self.settings.draw {
+            match centipawns {
+                Some(centipawns) if centipawns.abs() <= draw.score => self.drawn_plies += 1,
+                _ => self.drawn_plies = 0,
+            }
+
+            if moves >= draw.move_number && self.drawn_plies >= 2 * draw.moves {
+                return Some(Adjudication::Draw);
+            }
+        }
+
+        if self
+            .settings
+            .max_moves
+            .is_some_and(|max_moves| moves >= max_moves)
+        {
+            return Some(Adjudication::MaxMoves);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resign() {
+        let mut adjudicator = Adjudicator::new(AdjudicationSettings {
+            resign: Some(ResignAdjudication {
+                moves: 2,
+                score: 500,
+            }),
+            ..Default::default()
+        });
+
+        let losing = Some(UCIScore::Centipawns(-600));
+        let winning = Some(UCIScore::Centipawns(600));
+        assert_eq!(adjudicator.update(Color::White, losing, 1), None);
+        assert_eq!(adjudicator.update(Color::Black, winning, 2), None);
+        // A move without a score breaks the series.
+        assert_eq!(adjudicator.update(Color::White, None, 3), None);
+        assert_eq!(adjudicator.update(Color::Black, winning, 4), None);
+        assert_eq!(
+            adjudicator.update(Color::White, Some(UCIScore::Mate(-3)), 5),
+            None
+        );
+        assert_eq!(adjudicator.update(Color::Black, winning, 6), None);
+        assert_eq!(
+            adjudicator.update(Color::White, losing, 7),
+            Some(Adjudication::Resign(Color::White))
+        );
+    }
+
+    #[test]
+    fn draw() {
+        let mut adjudicator = Adjudicator::new(AdjudicationSettings {
+            draw: Some(DrawAdjudication {
+                move_number: 3,
+                moves: 1,
+                score: 10,
+            }),
+            ..Default::default()
+        });
+
+        let drawn = Some(UCIScore::Centipawns(-5));
+        assert_eq!(adjudicator.update(Color::White, drawn, 1), None);
+        // The game is too short to be adjudicated.
+        assert_eq!(adjudicator.update(Color::Black, drawn, 2), None);
+        assert_eq!(
+            adjudicator.update(Color::White, Some(UCIScore::Centipawns(50)), 3),
+            None
+        );
+        assert_eq!(adjudicator.update(Color::Black, drawn, 4), None);
+        assert_eq!(adjudicator.update(Color::White, drawn, 5), None);
+        assert_eq!(
+            adjudicator.update(Color::Black, drawn, 6),
+            Some(Adjudication::Draw)
+        );
+    }
+
+    #[test]
+    fn max_moves() {
+        let mut adjudicator = Adjudicator::new(AdjudicationSettings {
+            max_moves: Some(2),
+            ..Default::default()
+        });
+
+        assert_eq!(adjudicator.update(Color::White, None, 3), None);
+        assert_eq!(
+            adjudicator.update(Color::Black, None, 4),
+            Some(Adjudication::MaxMoves)
+        );
+    }
+}
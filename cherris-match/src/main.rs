@@ -0,0 +1,216 @@
+use std::{
+    env,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::Write,
+    process,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use cherris_core::{Color, generate_lookup_tables, pgn::pgn_result};
+
+use crate::{
+    match_game::play_game,
+    openings::{Opening, load_openings},
+    player::Player,
+    settings::{Settings, USAGE},
+    stats::{Score, SprtResult},
+    time_control::TimeControl,
+};
+
+mod adjudication;
+mod match_game;
+mod openings;
+mod player;
+mod settings;
+mod stats;
+mod time_control;
+
+fn main() {
+    let settings = Settings::from_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    });
+
+    generate_lookup_tables();
+
+    let openings = match &settings.openings {
+        Some(path) => load_openings(path, settings.opening_plies).unwrap_or_else(|err| exit(err)),
+        None => vec![Opening::default()],
+    };
+
+    // Every worker plays its games with its own pair of engines.
+    let workers = settings.concurrency.min(settings.games as usize);
+    let players: Vec<[Player; 2]> = (0..workers)
+        .map(|_| {
+            settings.engines.each_ref().map(|config| {
+                Player::spawn(config)
+                    .unwrap_or_else(|err| exit(format!("Failed to start {}: {}", config.path, err)))
+            })
+        })
+        .collect();
+    let names = player_names(&settings, &players[0]);
+    let mut pgn_file = settings.pgn.as_ref().map(|path| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|err| exit(format!("Failed to open {}: {}", path, err)))
+    });
+
+    println!(
+        "Playing {} games of {} vs {} at {}",
+        settings.games, names[0], names[1], settings.time_control
+    );
+
+    let settings = Arc::new(settings);
+    let openings = Arc::new(openings);
+    let next_game = Arc::new(AtomicU32::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, results) = mpsc::channel();
+
+    for mut players in players {
+        let settings = settings.clone();
+        let openings = openings.clone();
+        let next_game = next_game.clone();
+        let stop = stop.clone();
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            loop {
+                let index = next_game.fetch_add(1, Ordering::Relaxed);
+                if index >= settings.games || stop.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                for (player, config) in players.iter_mut().zip(&settings.engines) {
+                    if let Err(err) = player.new_game(config) {
+                        eprintln!("Failed to restart {}: {}", config.path, err);
+                        return;
+                    }
+                }
+
+                // Each opening is played twice, and the engines swap colors in the second game.
+                let opening = &openings[(index / 2) as usize % openings.len()];
+                let [first, second] = &mut players;
+                let played = match first_engine_color(index) {
+                    Color::White => play_game(first, second, opening, &settings),
+                    Color::Black => play_game(second, first, opening, &settings),
+                };
+
+                if sender.send((index, played)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(sender);
+
+    let mut score = Score::default();
+    for (index, mut played) in results {
+        let (white, black) = match first_engine_color(index) {
+            Color::White => (&names[0], &names[1]),
+            Color::Black => (&names[1], &names[0]),
+        };
+        let result = played.pgn.game.result();
+
+        played.pgn.set_tag("Event", "cherris-match");
+        played.pgn.set_tag("Round", &(index + 1).to_string());
+        played.pgn.set_tag("White", white);
+        played.pgn.set_tag("Black", black);
+        if let TimeControl::Clock { .. } = settings.time_control {
+            played
+                .pgn
+                .set_tag("TimeControl", &settings.time_control.to_string());
+        }
+        if let Some(file) = &mut pgn_file {
+            write_pgn(file, &played.pgn);
+        }
+
+        score.add(result, first_engine_color(index));
+        println!(
+            "Finished game {} ({} vs {}): {} {{{}}}",
+            index + 1,
+            white,
+            black,
+            pgn_result(result),
+            played.reason
+        );
+        println!("Score of {} vs {}: {}", names[0], names[1], score);
+
+        if let Some(sprt) = settings.sprt
+            && sprt.result(&score).is_some()
+        {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    if score.games() == 0 {
+        exit("No game was played");
+    }
+
+    println!(
+        "Elo difference: {:.1} +/- {:.1}",
+        score.elo(),
+        score.elo_error()
+    );
+    if let Some(sprt) = settings.sprt {
+        let decision = match sprt.result(&score) {
+            Some(SprtResult::H0) => " - H0 was accepted",
+            Some(SprtResult::H1) => " - H1 was accepted",
+            None => "",
+        };
+        println!(
+            "SPRT: llr {:.2}, lbound {:.2}, ubound {:.2}{}",
+            sprt.llr(&score),
+            sprt.lower_bound(),
+            sprt.upper_bound(),
+            decision
+        );
+    }
+}
+
+/// Returns the color the first engine plays in the game with the given index.
+fn first_engine_color(index: u32) -> Color {
+    if index.is_multiple_of(2) {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+/// Returns the names of the engines. Engines with the same name are numbered.
+fn player_names(settings: &Settings, players: &[Player; 2]) -> [String; 2] {
+    let names = [0, 1].map(|index| {
+        let config = &settings.engines[index];
+        config
+            .name
+            .as_deref()
+            .or(players[index].name())
+            .unwrap_or(&config.path)
+            .to_string()
+    });
+
+    if names[0] == names[1] {
+        [0, 1].map(|index| format!("{} {}", names[index], index + 1))
+    } else {
+        names
+    }
+}
+
+fn write_pgn(file: &mut File, pgn: &impl Display) {
+    if let Err(err) = writeln!(file, "{}", pgn) {
+        eprintln!("Failed to write the pgn: {}", err);
+    }
+}
+
+fn exit(err: impl Display) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
@@ -0,0 +1,166 @@
+use std::{io, time::Duration};
+
+use cherris_core::{Color, GameAction, GameResult, Move, pgn::Pgn, uci::UCIEngineCommand};
+
+use crate::{
+    adjudication::{Adjudication, Adjudicator},
+    openings::Opening,
+    player::Player,
+    settings::Settings,
+    time_control::{Clock, go_params},
+};
+
+/// A finished game of the match.
+pub struct PlayedGame {
+    /// The game with the evaluations of the engines as comments. The tags of the players are
+    /// left to the caller.
+    pub pgn: Pgn,
+    /// Why the game ended, for example `White mates`.
+    pub reason: String,
+}
+
+/// How a game ended, if not by the rules of chess alone.
+enum Termination {
+    Normal,
+    Adjudication(Adjudication),
+    /// The engine of the color ran out of time.
+    TimeForfeit(Color),
+    /// The engine of the color sent a move that isn't legal.
+    IllegalMove(Color, String),
+    /// The engine of the color stopped answering.
+    Disconnect(Color),
+}
+
+impl Termination {
+    /// Returns the value of the `Termination` tag of the PGN.
+    fn tag(&self) -> &'static str {
+        match self {
+            Termination::Normal => "normal",
+            Termination::Adjudication(_) => "adjudication",
+            Termination::TimeForfeit(_) => "time forfeit",
+            Termination::IllegalMove(..) => "rules infraction",
+            Termination::Disconnect(_) => "abandoned",
+        }
+    }
+
+    fn reason(&self, result: GameResult) -> String {
+        match (self, result) {
+            (Termination::Normal, GameResult::Win(winner)) => {
+                format!("{} mates", color_name(winner))
+            }
+            (Termination::Normal, GameResult::Draw(reason)) => format!("Draw by {}", reason),
+            (Termination::Adjudication(Adjudication::Resign(color)), _) => {
+                format!("{} resigns", color_name(*color))
+            }
+            (Termination::Adjudication(Adjudication::Draw), _) => {
+                "Draw by adjudication".to_string()
+            }
+            (Termination::Adjudication(Adjudication::MaxMoves), _) => {
+                "Draw by move limit".to_string()
+            }
+            (Termination::TimeForfeit(color), _) => {
+                format!("{} loses on time", color_name(*color))
+            }
+            (Termination::IllegalMove(color, chess_move), _) => {
+                format!(
+                    "{} makes an illegal move {}",
+                    color_name(*color),
+                    chess_move
+                )
+            }
+            (Termination::Disconnect(color), _) => {
+                format!("{} disconnects", color_name(*color))
+            }
+            (Termination::Normal, GameResult::Ongoing) => "Unterminated".to_string(),
+        }
+    }
+}
+
+/// Plays a game from the opening until it is over or adjudicated.
+pub fn play_game(
+    white: &mut Player,
+    black: &mut Player,
+    opening: &Opening,
+    settings: &Settings,
+) -> PlayedGame {
+    let mut game = opening.game();
+    let mut clocks = [Clock::new(settings.time_control, settings.time_margin); Color::COUNT];
+    let mut adjudicator = Adjudicator::new(settings.adjudication);
+    let mut evals = Vec::new();
+
+    let termination = loop {
+        if game.result() != GameResult::Ongoing {
+            break Termination::Normal;
+        }
+
+        let color = game.color_to_move();
+        let player = match color {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+        let go_params = go_params(&clocks[Color::White], &clocks[Color::Black], color);
+        let timeout = Duration::from_millis(clocks[color].deadline() as u64);
+
+        let search = match player.search(UCIEngineCommand::position_of(&game), go_params, timeout) {
+            Ok(search) => search,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                break Termination::TimeForfeit(color);
+            }
+            Err(_) => break Termination::Disconnect(color),
+        };
+        if !clocks[color].stop(search.elapsed) {
+            break Termination::TimeForfeit(color);
+        }
+        let Some(chess_move) = Move::from_uci(&search.best_move, game.position()) else {
+            break Termination::IllegalMove(color, search.best_move);
+        };
+
+        if let (Some(score), Some(depth)) = (search.score, search.depth) {
+            evals.push((game.move_history().len(), score, depth));
+        }
+        game.make_move(chess_move);
+
+        // Draws that need a claim are claimed right away.
+        if game.result() == GameResult::Ongoing && game.claimable_draw().is_some() {
+            game.apply(color, GameAction::ClaimDraw)
+                .expect("The draw is claimable");
+        }
+
+        if game.result() == GameResult::Ongoing
+            && let Some(adjudication) =
+                adjudicator.update(color, search.score, game.move_history().len())
+        {
+            break Termination::Adjudication(adjudication);
+        }
+    };
+
+    match termination {
+        Termination::Adjudication(Adjudication::Resign(color))
+        | Termination::TimeForfeit(color)
+        | Termination::IllegalMove(color, _)
+        | Termination::Disconnect(color) => {
+            game.apply(color, GameAction::Resign)
+                .expect("The game is ongoing");
+        }
+        Termination::Adjudication(Adjudication::Draw | Adjudication::MaxMoves) => {
+            game.adjudicate_draw().expect("The game is ongoing");
+        }
+        Termination::Normal => {}
+    }
+
+    let reason = termination.reason(game.result());
+    let mut pgn = Pgn::new(game);
+    pgn.set_tag("Termination", termination.tag());
+    for (ply, score, depth) in evals {
+        pgn.set_eval(ply, &score, depth);
+    }
+
+    PlayedGame { pgn, reason }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use cherris_core::{FenMode, Game, Move, Position, pgn::PgnReader};
+
+/// The position a game of the match starts from.
+#[derive(Clone, Default)]
+pub struct Opening {
+    pub position: Position,
+    /// The moves played from `position` before the engines take over.
+    pub moves: Vec<Move>,
+}
+
+impl Opening {
+    /// Returns a game in which the moves of the opening were played.
+    pub fn game(&self) -> Game {
+        let mut game = Game::from_position(self.position);
+        for chess_move in &self.moves {
+            game.make_move(*chess_move);
+        }
+
+        game
+    }
+}
+
+/// Reads the openings from an EPD or PGN file, depending on its extension. Only the first
+/// `plies` moves of the games in a PGN are used.
+pub fn load_openings(path: &str, plies: Option<usize>) -> Result<Vec<Opening>, String> {
+    let file = File::open(path).map_err(|err| format!("can't read {}: {}", path, err))?;
+    let reader = BufReader::new(file);
+
+    let openings = match Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("epd") => read_epd(reader)?,
+        Some("pgn") => read_pgn(reader, plies)?,
+        _ => return Err(format!("{} is neither an epd nor a pgn file", path)),
+    };
+
+    if openings.is_empty() {
+        return Err(format!("{} contains no openings", path));
+    }
+
+    Ok(openings)
+}
+
+/// Reads one position per line. The operations after the position are ignored.
+fn read_epd(reader: impl BufRead) -> Result<Vec<Opening>, String> {
+    reader
+        .lines()
+        .map(|line| line.map_err(|err| err.to_string()))
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+            let position = Position::from_fen(&line, FenMode::Lenient)
+                .map_err(|err| format!("invalid epd {}: {}", line, err))?;
+
+            Ok(Opening {
+                position,
+                moves: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn read_pgn(reader: impl BufRead, plies: Option<usize>) -> Result<Vec<Opening>, String> {
+    PgnReader::new(reader)
+        .map(|pgn| {
+            let pgn = pgn.map_err(|err| err.to_string())?;
+            let moves = pgn.game.move_history();
+            let plies = plies.unwrap_or(moves.len()).min(moves.len());
+
+            Ok(Opening {
+                position: *pgn.game.starting_position(),
+                moves: moves[..plies].to_vec(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cherris_core::generate_lookup_tables;
+
+    use super::*;
+
+    #[test]
+    fn epd() {
+        generate_lookup_tables();
+
+        let epd = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id \"e4\";\n\n\
+            r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3\n";
+        let openings = read_epd(epd.as_bytes()).unwrap();
+
+        assert_eq!(openings.len(), 2);
+        assert_eq!(
+            openings[1].game().position().to_string(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+        );
+        assert!(read_epd("not a position".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn pgn() {
+        generate_lookup_tables();
+
+        let pgn = "[Event \"?\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n\n[Event \"?\"]\n\n1. d4 *\n";
+        let openings = read_pgn(pgn.as_bytes(), Some(3)).unwrap();
+
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].moves.len(), 3);
+        assert_eq!(openings[1].moves.len(), 1);
+        assert_eq!(
+            openings[0].game().position().to_string(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use cherris_core::uci::{
+    UCIEngineCommand, UCIGoParams, UCIGuiCommand, UCIOptionParams, UCIScore, UCIScoreBound,
+    UCISearchInfo, UciEngineProcess,
+};
+
+use crate::settings::EngineConfig;
+
+/// The result of a search of a player.
+pub struct SearchResult {
+    pub best_move: String,
+    /// The last exact score the engine reported, from its own point of view.
+    pub score: Option<UCIScore>,
+    /// The depth of the last score.
+    pub depth: Option<u8>,
+    /// The time the search took in milliseconds.
+    pub elapsed: u128,
+}
+
+/// An engine that plays games of the match.
+pub struct Player {
+    engine: UciEngineProcess,
+}
+
+impl Player {
+    /// Starts the engine and sets its options.
+    pub fn spawn(config: &EngineConfig) -> io::Result<Player> {
+        let mut engine = UciEngineProcess::spawn(&config.path)?;
+        for option in &config.options {
+            engine.send(&UCIEngineCommand::SetOption(UCIOptionParams {
+                id: option.id.clone(),
+                value: option.value.clone(),
+            }))?;
+        }
+        engine.is_ready()?;
+
+        Ok(Player { engine })
+    }
+
+    /// The name the engine sent.
+    pub fn name(&self) -> Option<&str> {
+        self.engine.name()
+    }
+
+    /// Tells the engine that a new game starts. An engine that stopped answering is restarted.
+    pub fn new_game(&mut self, config: &EngineConfig) -> io::Result<()> {
+        let ready = self
+            .engine
+            .send(&UCIEngineCommand::UciNewGame)
+            .and_then(|_| self.engine.is_ready());
+
+        if ready.is_err() {
+            *self = Player::spawn(config)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lets the engine search the position until it sends its best move. If that takes longer
+    /// than `timeout`, the search is stopped and an error of the kind `TimedOut` is returned.
+    pub fn search(
+        &mut self,
+        position: UCIEngineCommand,
+        go_params: UCIGoParams,
+        timeout: Duration,
+    ) -> io::Result<SearchResult> {
+        self.engine.send(&position)?;
+        self.engine.send(&UCIEngineCommand::Go(go_params))?;
+        let start = Instant::now();
+        let deadline = start + timeout;
+
+        let mut score = None;
+        let mut depth = None;
+        loop {
+            let command = self
+                .engine
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()));
+
+            match command {
+                // Bounds of aspiration window fail highs and lows aren't the score of the position.
+                Ok(UCIGuiCommand::Info(UCISearchInfo {
+                    score: Some(info_score),
+                    bound: UCIScoreBound::Exact,
+                    depth: info_depth,
+                    ..
+                })) => {
+                    score = Some(info_score);
+                    depth = info_depth;
+                }
+                Ok(UCIGuiCommand::BestMove { chess_move, .. }) => {
+                    return Ok(SearchResult {
+                        best_move: chess_move,
+                        score,
+                        depth,
+                        elapsed: start.elapsed().as_millis(),
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    // The best move of a search that took too long must not be read as the move
+                    // of the next search.
+                    if err.kind() == io::ErrorKind::TimedOut {
+                        let _ = self.engine.stop();
+                    }
+
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::{fmt::Display, str::FromStr};
+
+use cherris_core::uci::UCIOptionParams;
+
+use crate::{
+    adjudication::{AdjudicationSettings, DrawAdjudication, ResignAdjudication},
+    stats::Sprt,
+    time_control::TimeControl,
+};
+
+/// The number of games played if none is given.
+const DEFAULT_GAMES: u32 = 100;
+/// The time control used if none is given.
+const DEFAULT_TIME_CONTROL: TimeControl = TimeControl::Clock {
+    moves: None,
+    base: 10000,
+    increment: 100,
+};
+/// The time in milliseconds an engine may go over its time if no margin is given.
+const DEFAULT_TIME_MARGIN: u128 = 50;
+/// The error probabilities of an SPRT if none are given.
+const DEFAULT_SPRT_ALPHA: f64 = 0.05;
+const DEFAULT_SPRT_BETA: f64 = 0.05;
+
+pub const USAGE: &str = "usage: cherris-match --engine1 <path> --engine2 <path> \
+[--name1 <name>] [--name2 <name>] [--option1 <name>=<value>]... [--option2 <name>=<value>]... \
+[--games <n>] [--concurrency <n>] [--tc <[moves/]seconds[+increment]>] [--movetime <ms>] \
+[--timemargin <ms>] [--openings <path.epd|path.pgn>] [--plies <n>] [--pgn <path>] \
+[--resign <moves>,<cp>] [--draw <move number>,<moves>,<cp>] [--maxmoves <n>] \
+[--sprt <elo0>,<elo1>[,<alpha>,<beta>]]";
+
+/// An engine of the match.
+#[derive(Debug, Default, PartialEq)]
+pub struct EngineConfig {
+    pub path: String,
+    /// The name in the PGN. Without one the name the engine sends is used.
+    pub name: Option<String>,
+    /// The options that are set before the first game.
+    pub options: Vec<UCIOptionParams>,
+}
+
+/// The settings the match is started with.
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub engines: [EngineConfig; 2],
+    pub games: u32,
+    /// The number of games that are played at the same time.
+    pub concurrency: usize,
+    pub time_control: TimeControl,
+    /// The time in milliseconds an engine may go over its time before it loses.
+    pub time_margin: u128,
+    /// An EPD or PGN file with the openings. Each opening is played twice, with swapped colors.
+    pub openings: Option<String>,
+    /// The number of moves of the games in a PGN that are used as the opening.
+    pub opening_plies: Option<usize>,
+    /// The file the games are appended to.
+    pub pgn: Option<String>,
+    pub adjudication: AdjudicationSettings,
+    /// The test that stops the match once it knows which engine is stronger.
+    pub sprt: Option<Sprt>,
+}
+
+impl Settings {
+    /// Reads the settings from the command line.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+        let mut settings = Settings {
+            engines: Default::default(),
+            games: DEFAULT_GAMES,
+            concurrency: 1,
+            time_control: DEFAULT_TIME_CONTROL,
+            time_margin: DEFAULT_TIME_MARGIN,
+            openings: None,
+            opening_plies: None,
+            pgn: None,
+            adjudication: AdjudicationSettings::default(),
+            sprt: None,
+        };
+
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unknown argument {}", arg))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+
+            settings.set(key, &value)?;
+        }
+
+        for (index, engine) in settings.engines.iter().enumerate() {
+            if engine.path.is_empty() {
+                return Err(format!("missing engine{}", index + 1));
+            }
+        }
+
+        Ok(settings)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "engine1" | "engine2" => self.engine(key)?.path = value.to_string(),
+            "name1" | "name2" => self.engine(key)?.name = Some(value.to_string()),
+            "option1" | "option2" => {
+                let (id, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid option {}", value))?;
+                self.engine(key)?.options.push(UCIOptionParams {
+                    id: id.to_string(),
+                    value: Some(value.to_string()),
+                });
+            }
+            "games" => self.games = parse(key, value).and_then(positive)?,
+            "concurrency" => self.concurrency = parse(key, value).and_then(positive)?,
+            "tc" => self.time_control = TimeControl::from_str(value)?,
+            "movetime" => {
+                self.time_control = TimeControl::MoveTime(parse(key, value).and_then(positive)?)
+            }
+            "timemargin" => self.time_margin = parse(key, value)?,
+            "openings" => self.openings = Some(value.to_string()),
+            "plies" => self.opening_plies = Some(parse(key, value)?),
+            "pgn" => self.pgn = Some(value.to_string()),
+            "resign" => {
+                let [moves, score] = parse_list(key, value)?;
+                self.adjudication.resign = Some(ResignAdjudication {
+                    moves: positive(moves)? as u32,
+                    score,
+                });
+            }
+            "draw" => {
+                let [move_number, moves, score] = parse_list(key, value)?;
+                self.adjudication.draw = Some(DrawAdjudication {
+                    move_number: move_number.max(0) as u32,
+                    moves: positive(moves)? as u32,
+                    score,
+                });
+            }
+            "maxmoves" => self.adjudication.max_moves = Some(parse(key, value).and_then(positive)?),
+            "sprt" => {
+                let bounds: Vec<f64> = value
+                    .split(',')
+                    .map(|bound| parse(key, bound))
+                    .collect::<Result<_, _>>()?;
+                let (elo0, elo1, alpha, beta) = match bounds.as_slice() {
+                    [elo0, elo1] => (*elo0, *elo1, DEFAULT_SPRT_ALPHA, DEFAULT_SPRT_BETA),
+                    [elo0, elo1, alpha, beta] => (*elo0, *elo1, *alpha, *beta),
+                    _ => return Err(format!("invalid value {} for sprt", value)),
+                };
+                if elo0 >= elo1 || !(0.0..1.0).contains(&alpha) || !(0.0..1.0).contains(&beta) {
+                    return Err(format!("invalid value {} for sprt", value));
+                }
+
+                self.sprt = Some(Sprt {
+                    elo0,
+                    elo1,
+                    alpha,
+                    beta,
+                });
+            }
+            _ => return Err(format!("unknown setting {}", key)),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the engine a setting ending in 1 or 2 belongs to.
+    fn engine(&mut self, key: &str) -> Result<&mut EngineConfig, String> {
+        match key.chars().last() {
+            Some('1') => Ok(&mut self.engines[0]),
+            Some('2') => Ok(&mut self.engines[1]),
+            _ => Err(format!("unknown setting {}", key)),
+        }
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid value {} for {}", value, key))
+}
+
+/// Parses a value made of `N` comma separated numbers.
+fn parse_list<const N: usize>(key: &str, value: &str) -> Result<[i32; N], String> {
+    let numbers: Vec<i32> = value
+        .split(',')
+        .map(|number| parse(key, number))
+        .collect::<Result<_, _>>()?;
+
+    numbers
+        .try_into()
+        .map_err(|_| format!("invalid value {} for {}", value, key))
+}
+
+fn positive<T: Default + Display + PartialOrd>(value: T) -> Result<T, String> {
+    if value > T::default() {
+        Ok(value)
+    } else {
+        Err(format!("{} must be positive", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_args(args: &str) -> Result<Settings, String> {
+        Settings::from_args(args.split_whitespace().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn engines() {
+        let settings = from_args(
+            "--engine1 ./old --engine2 ./new --name2 new --option1 Hash=16 --option1 Threads=2",
+        )
+        .unwrap();
+
+        assert_eq!(settings.engines[0].path, "./old");
+        assert_eq!(settings.engines[0].name, None);
+        assert_eq!(
+            settings.engines[0].options,
+            vec![
+                UCIOptionParams {
+                    id: "Hash".to_string(),
+                    value: Some("16".to_string()),
+                },
+                UCIOptionParams {
+                    id: "Threads".to_string(),
+                    value: Some("2".to_string()),
+                },
+            ]
+        );
+        assert_eq!(settings.engines[1].name.as_deref(), Some("new"));
+        assert_eq!(settings.games, DEFAULT_GAMES);
+        assert_eq!(settings.time_control, DEFAULT_TIME_CONTROL);
+    }
+
+    #[test]
+    fn missing_engine() {
+        assert_eq!(
+            from_args("--engine1 ./old"),
+            Err("missing engine2".to_string())
+        );
+    }
+
+    #[test]
+    fn match_settings() {
+        let settings = from_args(
+            "--engine1 a --engine2 b --games 20 --concurrency 4 --movetime 100 --resign 3,600 \
+            --draw 40,8,10 --maxmoves 200 --sprt 0,5",
+        )
+        .unwrap();
+
+        assert_eq!(settings.games, 20);
+        assert_eq!(settings.concurrency, 4);
+        assert_eq!(settings.time_control, TimeControl::MoveTime(100));
+        assert_eq!(
+            settings.adjudication,
+            AdjudicationSettings {
+                resign: Some(ResignAdjudication {
+                    moves: 3,
+                    score: 600,
+                }),
+                draw: Some(DrawAdjudication {
+                    move_number: 40,
+                    moves: 8,
+                    score: 10,
+                }),
+                max_moves: Some(200),
+            }
+        );
+        assert_eq!(
+            settings.sprt,
+            Some(Sprt {
+                elo0: 0.0,
+                elo1: 5.0,
+                alpha: DEFAULT_SPRT_ALPHA,
+                beta: DEFAULT_SPRT_BETA,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_settings() {
+        for args in [
+            "--engine1 a --engine2 b --games 0",
+            "--engine1 a --engine2 b --resign 3",
+            "--engine1 a --engine2 b --sprt 5,0",
+            "--engine1 a --engine2 b --option1 Hash",
+            "--engine1 a --engine2 b --engine3 c",
+            "--engine1 a --engine2 b --tc fast",
+            "--engine1 a --engine2",
+        ] {
+            assert!(from_args(args).is_err(), "{}", args);
+        }
+    }
+}
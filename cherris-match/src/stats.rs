@@ -0,0 +1,243 @@
+use std::fmt::Display;
+
+use cherris_core::{Color, GameResult};
+
+/// The quantile of the normal distribution for a 95% confidence interval.
+const NORMAL_QUANTILE_95: f64 = 1.959964;
+
+/// The results of the first engine of a match.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Adds the result of a game in which the first engine played `color`.
+    pub fn add(&mut self, result: GameResult, color: Color) {
+        match result {
+            GameResult::Win(winner) if winner == color => self.wins += 1,
+            GameResult::Win(_) => self.losses += 1,
+            GameResult::Draw(_) => self.draws += 1,
+            GameResult::Ongoing => {}
+        }
+    }
+
+    /// Returns the points per game of the first engine, between 0 and 1.
+    pub fn ratio(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Returns the variance of the points of a single game.
+    fn variance(&self) -> f64 {
+        let ratio = self.ratio();
+
+        (self.wins as f64 * (1.0 - ratio).powi(2)
+            + self.losses as f64 * ratio.powi(2)
+            + self.draws as f64 * (0.5 - ratio).powi(2))
+            / self.games() as f64
+    }
+
+    /// Returns the Elo difference between the first and the second engine.
+    pub fn elo(&self) -> f64 {
+        elo_difference(self.ratio())
+    }
+
+    /// Returns the margin of error of `elo` with a confidence of 95%.
+    pub fn elo_error(&self) -> f64 {
+        let deviation = (self.variance() / self.games() as f64).sqrt();
+        let low = self.ratio() - NORMAL_QUANTILE_95 * deviation;
+        let high = self.ratio() + NORMAL_QUANTILE_95 * deviation;
+
+        if low <= 0.0 || high >= 1.0 {
+            return f64::INFINITY;
+        }
+
+        (elo_difference(high) - elo_difference(low)) / 2.0
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} - {} - {}  [{:.3}] {}",
+            self.wins,
+            self.losses,
+            self.draws,
+            self.ratio(),
+            self.games()
+        )
+    }
+}
+
+/// Converts points per game into an Elo difference.
+fn elo_difference(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+/// Converts an Elo difference into the expected points per game.
+fn expected_ratio(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// A sequential probability ratio test that decides whether the first engine is `elo0` or
+/// `elo1` Elo stronger than the second one, with the error probabilities `alpha` and `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/// The hypothesis an SPRT accepted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtResult {
+    /// The first engine is at most `elo0` Elo stronger.
+    H0,
+    /// The first engine is at least `elo1` Elo stronger.
+    H1,
+}
+
+impl Sprt {
+    /// Returns the log-likelihood ratio of the score, using the normal approximation of the
+    /// generalized SPRT.
+    pub fn llr(&self, score: &Score) -> f64 {
+        let variance = score.variance();
+        if score.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+
+        let ratio0 = expected_ratio(self.elo0);
+        let ratio1 = expected_ratio(self.elo1);
+
+        score.games() as f64 * (ratio1 - ratio0) * (2.0 * score.ratio() - ratio0 - ratio1)
+            / (2.0 * variance)
+    }
+
+    /// The LLR below which H0 is accepted.
+    pub fn lower_bound(&self) -> f64 {
+        (self.beta / (1.0 - self.alpha)).ln()
+    }
+
+    /// The LLR above which H1 is accepted.
+    pub fn upper_bound(&self) -> f64 {
+        ((1.0 - self.beta) / self.alpha).ln()
+    }
+
+    /// Returns the accepted hypothesis once the LLR crossed one of the bounds.
+    pub fn result(&self, score: &Score) -> Option<SprtResult> {
+        let llr = self.llr(score);
+
+        if llr <= self.lower_bound() {
+            Some(SprtResult::H0)
+        } else if llr >= self.upper_bound() {
+            Some(SprtResult::H1)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cherris_core::DrawReason;
+
+    use super::*;
+
+    const SCORE: Score = Score {
+        wins: 60,
+        losses: 40,
+        draws: 100,
+    };
+
+    #[test]
+    fn add() {
+        let mut score = Score::default();
+        score.add(GameResult::Win(Color::White), Color::White);
+        score.add(GameResult::Win(Color::White), Color::Black);
+        score.add(GameResult::Draw(DrawReason::Stalemate), Color::Black);
+
+        assert_eq!(
+            score,
+            Score {
+                wins: 1,
+                losses: 1,
+                draws: 1,
+            }
+        );
+        assert_eq!(score.to_string(), "1 - 1 - 1  [0.500] 3");
+    }
+
+    #[test]
+    fn elo() {
+        assert!((SCORE.elo() - 34.86).abs() < 0.01);
+        assert!((SCORE.elo_error() - 34.16).abs() < 0.01);
+        assert_eq!(
+            Score {
+                wins: 5,
+                losses: 5,
+                draws: 0,
+            }
+            .elo(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn elo_error_without_losses() {
+        let score = Score {
+            wins: 3,
+            losses: 0,
+            draws: 0,
+        };
+
+        assert_eq!(score.elo(), f64::INFINITY);
+        assert_eq!(score.elo_error(), f64::INFINITY);
+    }
+
+    #[test]
+    fn llr() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+
+        assert!((sprt.llr(&SCORE) - 1.0055).abs() < 0.001);
+        assert!((sprt.lower_bound() + 2.944).abs() < 0.001);
+        assert!((sprt.upper_bound() - 2.944).abs() < 0.001);
+        assert_eq!(sprt.result(&SCORE), None);
+        assert_eq!(sprt.llr(&Score::default()), 0.0);
+    }
+
+    #[test]
+    fn sprt_result() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        let stronger = Score {
+            wins: 600,
+            losses: 400,
+            draws: 1000,
+        };
+        let weaker = Score {
+            wins: 400,
+            losses: 600,
+            draws: 1000,
+        };
+
+        assert_eq!(sprt.result(&stronger), Some(SprtResult::H1));
+        assert_eq!(sprt.result(&weaker), Some(SprtResult::H0));
+    }
+}
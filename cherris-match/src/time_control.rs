@@ -0,0 +1,251 @@
+use std::{fmt::Display, str::FromStr};
+
+use cherris_core::{Color, uci::UCIGoParams};
+
+/// How much time the engines get for their moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeControl {
+    /// A clock that starts with `base` milliseconds and gets `increment` milliseconds after each
+    /// move. With `moves`, `base` is added again every time that many moves were played.
+    Clock {
+        moves: Option<u32>,
+        base: u128,
+        increment: u128,
+    },
+    /// A fixed time in milliseconds for each move.
+    MoveTime(u64),
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Parses a clock written as `[moves/]seconds[+increment]`, like `40/60`, `10+0.1` or `5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time control {}", s);
+        let milliseconds = |seconds: &str| {
+            seconds
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .map(|seconds| (seconds * 1000.0).round() as u128)
+                .ok_or_else(invalid)
+        };
+
+        let (moves, clock) = match s.split_once('/') {
+            Some((moves, clock)) => (Some(moves.parse().map_err(|_| invalid())?), clock),
+            None => (None, s),
+        };
+        let (base, increment) = match clock.split_once('+') {
+            Some((base, increment)) => (milliseconds(base)?, milliseconds(increment)?),
+            None => (milliseconds(clock)?, 0),
+        };
+
+        if base == 0 || moves == Some(0) {
+            return Err(invalid());
+        }
+
+        Ok(TimeControl::Clock {
+            moves,
+            base,
+            increment,
+        })
+    }
+}
+
+impl Display for TimeControl {
+    /// Writes the time control like the `TimeControl` tag of a PGN.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeControl::Clock {
+                moves,
+                base,
+                increment,
+            } => {
+                if let Some(moves) = moves {
+                    write!(f, "{}/", moves)?;
+                }
+                write!(f, "{}", *base as f64 / 1000.0)?;
+                if *increment > 0 {
+                    write!(f, "+{}", *increment as f64 / 1000.0)?;
+                }
+
+                Ok(())
+            }
+            TimeControl::MoveTime(movetime) => write!(f, "movetime {}", movetime),
+        }
+    }
+}
+
+/// The clock of one engine in a game.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    time_control: TimeControl,
+    /// The time in milliseconds the engine may go over its time before it loses.
+    margin: u128,
+    time_left: u128,
+    moves_played: u32,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl, margin: u128) -> Clock {
+        let time_left = match time_control {
+            TimeControl::Clock { base, .. } => base,
+            TimeControl::MoveTime(movetime) => movetime as u128,
+        };
+
+        Clock {
+            time_control,
+            margin,
+            time_left,
+            moves_played: 0,
+        }
+    }
+
+    /// Returns how long the engine may think about its next move before it loses on time.
+    pub fn deadline(&self) -> u128 {
+        self.time_left + self.margin
+    }
+
+    /// Subtracts the time the engine needed for its move. Returns false if the engine ran out of
+    /// time.
+    pub fn stop(&mut self, elapsed: u128) -> bool {
+        if elapsed > self.deadline() {
+            return false;
+        }
+
+        if let TimeControl::Clock {
+            moves,
+            base,
+            increment,
+        } = self.time_control
+        {
+            self.time_left = self.time_left.saturating_sub(elapsed) + increment;
+            self.moves_played += 1;
+            if moves.is_some_and(|moves| self.moves_played.is_multiple_of(moves)) {
+                self.time_left += base;
+            }
+        }
+
+        true
+    }
+
+    fn moves_to_go(&self) -> Option<u64> {
+        match self.time_control {
+            TimeControl::Clock {
+                moves: Some(moves), ..
+            } => Some((moves - self.moves_played % moves) as u64),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the parameters of the `go` command for the engine of `color_to_move`.
+pub fn go_params(white: &Clock, black: &Clock, color_to_move: Color) -> UCIGoParams {
+    let clock = match color_to_move {
+        Color::White => white,
+        Color::Black => black,
+    };
+
+    match white.time_control {
+        TimeControl::Clock { increment, .. } => UCIGoParams {
+            w_time: Some(white.time_left),
+            b_time: Some(black.time_left),
+            w_inc: Some(increment).filter(|increment| *increment > 0),
+            b_inc: Some(increment).filter(|increment| *increment > 0),
+            moves_to_go: clock.moves_to_go(),
+            ..Default::default()
+        },
+        TimeControl::MoveTime(movetime) => UCIGoParams {
+            movetime: Some(movetime),
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            TimeControl::from_str("10+0.1"),
+            Ok(TimeControl::Clock {
+                moves: None,
+                base: 10000,
+                increment: 100,
+            })
+        );
+        assert_eq!(
+            TimeControl::from_str("40/60"),
+            Ok(TimeControl::Clock {
+                moves: Some(40),
+                base: 60000,
+                increment: 0,
+            })
+        );
+        assert!(TimeControl::from_str("0+1").is_err());
+        assert!(TimeControl::from_str("0/60").is_err());
+        assert!(TimeControl::from_str("ten").is_err());
+        assert!(TimeControl::from_str("10+-1").is_err());
+    }
+
+    #[test]
+    fn display() {
+        for time_control in ["10+0.1", "40/60", "0.5"] {
+            assert_eq!(
+                TimeControl::from_str(time_control).unwrap().to_string(),
+                time_control
+            );
+        }
+    }
+
+    #[test]
+    fn clock_increment() {
+        let mut clock = Clock::new(TimeControl::from_str("1+0.1").unwrap(), 0);
+
+        assert!(clock.stop(300));
+        assert_eq!(clock.time_left, 800);
+        assert!(!clock.stop(801));
+    }
+
+    #[test]
+    fn clock_moves() {
+        let mut clock = Clock::new(TimeControl::from_str("2/1").unwrap(), 0);
+
+        assert_eq!(clock.moves_to_go(), Some(2));
+        assert!(clock.stop(400));
+        assert_eq!(clock.moves_to_go(), Some(1));
+        assert!(clock.stop(400));
+        assert_eq!(clock.time_left, 1200);
+        assert_eq!(clock.moves_to_go(), Some(2));
+    }
+
+    #[test]
+    fn clock_margin() {
+        let mut clock = Clock::new(TimeControl::MoveTime(100), 50);
+
+        assert!(clock.stop(150));
+        assert!(!clock.stop(151));
+    }
+
+    #[test]
+    fn go() {
+        let white = Clock::new(TimeControl::from_str("40/60+1").unwrap(), 0);
+        let mut black = white;
+        black.stop(5000);
+
+        let params = go_params(&white, &black, Color::White);
+        assert_eq!(params.w_time, Some(60000));
+        assert_eq!(params.b_time, Some(56000));
+        assert_eq!(params.w_inc, Some(1000));
+        assert_eq!(params.moves_to_go, Some(40));
+        assert_eq!(
+            go_params(&white, &black, Color::Black).moves_to_go,
+            Some(39)
+        );
+
+        let clock = Clock::new(TimeControl::MoveTime(200), 0);
+        assert_eq!(go_params(&clock, &clock, Color::White).movetime, Some(200));
+    }
+}